@@ -1 +1,2 @@
+mod macros;
 mod vec;
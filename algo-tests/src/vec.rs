@@ -48,3 +48,87 @@ fn f5() {
     v1.push(100);
     println!("{v1:?}");
 }
+
+fn f6() {
+    let v1: Vec<_> = (0..100).collect();
+    let p = &raw const v1;
+    let first = v1.first();
+    match first {
+        Some(v) => println!("{v}"),
+        None => println!("empty"),
+    }
+    println!("{p:?}");
+}
+
+fn f7() {
+    let v1: Vec<_> = (0..100).collect();
+    let _ = v1;
+    match v1 {
+        ref v if v.is_empty() => println!("empty"),
+        _ => println!("{v1:?}"),
+    }
+    let _v2 = v1;
+}
+
+fn f8() {
+    let mut v1: Vec<_> = (0..100).collect();
+    let v2: Vec<_> = (0..10).collect();
+    let mut push = |x| v1.push(x);
+    push(v2.len());
+    push(v2[0]);
+    let owned = move || v2.len();
+    println!("{v1:?}");
+    println!("{}", owned());
+}
+
+fn f9() {
+    let mut v1: (Vec<u8>, Vec<u8>, Vec<u8>) = (Vec::new(), Vec::new(), Vec::new());
+    let first = &mut v1.0;
+    let second = &v1.1;
+    first.push(1);
+    println!("{second:?}");
+    let third = v1.2;
+    println!("{:?} {third:?}", v1.0);
+}
+
+fn f10() {
+    let mut v1: Vec<u8> = Vec::new();
+    let r1 = &mut v1;
+    let r2 = &mut *r1;
+    r2.push(1);
+    let first = r1.first();
+    println!("{first:?}");
+}
+
+async fn yield_now() {}
+
+async fn f11() {
+    let v1: Vec<u8> = Vec::new();
+    yield_now().await;
+    let mut len = v1.len();
+    while len < 3 {
+        len += 1;
+        yield_now().await;
+    }
+    drop(v1);
+    yield_now().await;
+    println!("{len}");
+}
+
+fn f12(n: usize) {
+    let v1: Vec<u8> = Vec::new();
+    let mut held: Option<&Vec<u8>> = None;
+    for _ in 0..n {
+        if let Some(r) = held {
+            println!("{r:?}");
+        }
+        held = Some(&v1);
+    }
+}
+
+fn f13<'a>(v1: &'a Vec<u8>, v2: &Vec<u8>) -> &'a u8 {
+    let first = v1.first().unwrap();
+    let len = v2.len();
+    println!("{len}");
+    first
+}
@@ -6,23 +6,33 @@ if not vim.g.loaded_rustowl then
   local default_highlight_styles = {
     definitely_live = 'underline',
     maybe_initialized = 'undercurl',
+    read = 'underline',
     imm_borrow = 'underline',
     mut_borrow = 'underline',
+    raw_ptr = 'underline',
     move = 'underline',
     call = 'underline',
+    held_across_await = 'undercurl',
     shared_mut = 'undercurl',
     outlive = 'undercurl',
+    conflict = 'underline',
+    lifetime_param = 'underline',
   }
 
   local default_colors = {
     definitely_live = '#00cc00',
     maybe_initialized = '#00cc00',
+    read = '#0000cc',
     imm_borrow = '#0000cc',
     mut_borrow = '#cc00cc',
+    raw_ptr = '#cc00cc',
     move = '#cccc00',
     call = '#cccc00',
+    held_across_await = '#cc0000',
     shared_mut = '#cc0000',
     outlive = '#cc0000',
+    conflict = '#cc0000',
+    lifetime_param = '#00cc00',
   }
 
   -- Ensure all keys are present even if user provides a partial table
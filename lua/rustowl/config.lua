@@ -29,20 +29,35 @@
 ---Highlight style for maybe initialized (default: 'undercurl')
 ---@field maybe_initialized? string
 ---
+---Highlight style for reads without moving, e.g. `let _ = x` (default: 'underline')
+---@field read? string
+---
 ---Highlight style for immutable borrow (default: 'underline')
 ---@field imm_borrow? string
 ---
 ---Highlight style for mutable borrow (default: 'underline')
 ---@field mut_borrow? string
 ---
+---Highlight style for raw pointer creation (default: 'underline')
+---@field raw_ptr? string
+---
 ---Highlight style for move (default: 'underline')
 ---@field move? string
 ---
 ---Highlight style for function call (default: 'underline')
 ---@field call? string
 ---
+---Highlight style for values held across `.await` (default: 'undercurl')
+---@field held_across_await? string
+---
 ---Highlight style for outlive (default: 'undercurl')
 ---@field outlive? string
+---
+---Highlight style for borrow-check conflicts (default: 'underline')
+---@field conflict? string
+---
+---Highlight style for variables constrained by the selected lifetime parameter (default: 'underline')
+---@field lifetime_param? string
 
 ---@class rustowl.Colors
 ---
@@ -52,20 +67,35 @@
 ---Color for maybe initialized highlights (default: '#00cc00')
 ---@field maybe_initialized? string
 ---
+---Color for reads without moving, e.g. `let _ = x` (default: '#0000cc')
+---@field read? string
+---
 ---Color for immutable borrow highlights (default: '#0000cc')
 ---@field imm_borrow? string
 ---
 ---Color for mutable borrow highlights (default: '#cc00cc')
 ---@field mut_borrow? string
 ---
+---Color for raw pointer creation highlights (default: '#cc00cc')
+---@field raw_ptr? string
+---
 ---Color for move highlights (default: '#cccc00')
 ---@field move? string
 ---
 ---Color for function call highlights (default: '#cccc00')
 ---@field call? string
 ---
+---Color for values held across `.await` (default: '#cc0000')
+---@field held_across_await? string
+---
 ---Color for outlive error highlights (default: '#cc0000')
 ---@field outlive? string
+---
+---Color for borrow-check conflict highlights (default: '#cc0000')
+---@field conflict? string
+---
+---Color for variables constrained by the selected lifetime parameter (default: '#00cc00')
+---@field lifetime_param? string
 
 ---NOTE: This allows lua-language-server to provide users
 ---completions and hover when setting vim.g.rustowl directly.
@@ -94,24 +124,34 @@ local default_config = {
   highlight_styles = {
     definitely_live = 'underline',
     maybe_initialized = 'undercurl',
+    read = 'underline',
     imm_borrow = 'underline',
     mut_borrow = 'underline',
+    raw_ptr = 'underline',
     move = 'underline',
     call = 'underline',
+    held_across_await = 'undercurl',
     shared_mut = 'undercurl',
     outlive = 'undercurl',
+    conflict = 'underline',
+    lifetime_param = 'underline',
   },
 
   ---@type rustowl.Colors
   colors = {
     definitely_live = '#00cc00',
     maybe_initialized = '#00cc00',
+    read = '#0000cc',
     imm_borrow = '#0000cc',
     mut_borrow = '#cc00cc',
+    raw_ptr = '#cc00cc',
     move = '#cccc00',
     call = '#cccc00',
+    held_across_await = '#cc0000',
     shared_mut = '#cc0000',
     outlive = '#cc0000',
+    conflict = '#cc0000',
+    lifetime_param = '#00cc00',
   },
 
   ---@class rustowl.internal.ClientConfig: vim.lsp.ClientConfig
@@ -82,18 +82,21 @@
                    (overlapped (gethash "overlapped" deco)))
               (if (not overlapped)
                   (cond
-                   ((equal type "definitely_live")
+                   ((or (equal type "definitely_live") (equal type "lifetime_param"))
                     (rustowl-underline start-pos end-pos "#00cc00" nil))
                    ((equal type "maybe_initialized")
                     (rustowl-underline start-pos end-pos "#00cc00" t))
-                   ((equal type "imm_borrow")
+                   ((or (equal type "imm_borrow") (equal type "read"))
                     (rustowl-underline start-pos end-pos "#0000cc" nil))
-                   ((equal type "mut_borrow")
+                   ((or (equal type "mut_borrow") (equal type "raw_ptr"))
                     (rustowl-underline start-pos end-pos "#cc00cc" nil))
                    ((or (equal type "move") (equal type "call"))
                     (rustowl-underline start-pos end-pos "#cccc00" nil))
-                   ((or (equal type "shared_mut") (equal type "outlive"))
-                    (rustowl-underline start-pos end-pos "#cc0000" t))))))
+                   ((or (equal type "shared_mut") (equal type "outlive")
+                        (equal type "held_across_await"))
+                    (rustowl-underline start-pos end-pos "#cc0000" t))
+                   ((equal type "conflict")
+                    (rustowl-underline start-pos end-pos "#cc0000" nil))))))
           decorations)))
      :mode 'current)))
 
@@ -1,14 +1,18 @@
 mod dataflow_analyzer;
 mod polonius_analyzer;
 
-use super::cache;
 pub use super::compiler::*;
+use super::{budget, cache};
 use indexmap::IndexMap;
+use rustowl::config::Exclusion;
 use rustowl::models::*;
 use std::collections::HashMap;
 use std::future::Future;
 use std::path::PathBuf;
 use std::pin::Pin;
+use std::sync::LazyLock;
+
+static EXCLUSION: LazyLock<Exclusion> = LazyLock::new(Exclusion::from_env);
 
 pub type MirAnalyzeFuture = Pin<Box<dyn Future<Output = MirAnalyzer> + Send + Sync>>;
 
@@ -17,12 +21,73 @@ pub struct AnalyzeResult {
     pub file_path: PathBuf,
     pub file_hash: String,
     pub mir_hash: String,
+    pub span: Range,
     pub analyzed: Function,
+    /// The result was reused from the incremental cache
+    pub cached: bool,
+}
+
+/// A function which has no analysis result
+#[derive(Clone, Debug)]
+pub struct SkipResult {
+    pub file_path: PathBuf,
+    pub span: Range,
+    pub fn_id: u32,
+    pub name: String,
+    pub reason: SkipReason,
+}
+impl SkipResult {
+    pub fn item(self) -> Item {
+        Item::Skipped {
+            span: self.span,
+            fn_id: self.fn_id,
+            name: self.name,
+            reason: self.reason,
+        }
+    }
+}
+
+/// Locals of a body which the closures defined in it are linked to
+struct BodyLinks {
+    variables: HashMap<Span, LocalId>,
+    holders: HashMap<DefId, LocalId>,
+}
+
+/// Link a closure or coroutine body to the locals of its enclosing functions
+fn closure_link(
+    tcx: TyCtxt<'_>,
+    fn_id: DefId,
+    source_info: &SourceInfo,
+    links: &HashMap<DefId, BodyLinks>,
+) -> Option<MirClosure> {
+    let parent = tcx.closure_parent(fn_id)?;
+    let holder = links
+        .get(&parent)
+        .and_then(|v| v.holders.get(&fn_id))
+        .map(|local| FnLocal::new(local.as_u32(), parent.as_u32()));
+    let captures = tcx.closure_captures(fn_id, source_info, |span| {
+        // a nested closure captures variables of outer functions through its parents
+        let mut body = Some(parent);
+        while let Some(id) = body {
+            if let Some(local) = links.get(&id).and_then(|v| v.variables.get(&span)) {
+                return Some(FnLocal::new(local.as_u32(), id.as_u32()));
+            }
+            body = tcx.closure_parent(id);
+        }
+        None
+    });
+    Some(MirClosure {
+        parent: parent.as_u32(),
+        holder,
+        captures,
+    })
 }
 
 pub enum MirAnalyzerInitResult {
-    Cached(AnalyzeResult),
-    Analyzer(MirAnalyzeFuture),
+    Cached(Box<AnalyzeResult>),
+    /// Analyzer to be run, with the result reported if the analysis fails
+    Analyzer(MirAnalyzeFuture, SkipResult),
+    Skipped(SkipResult),
 }
 
 pub struct MirAnalyzer {
@@ -33,12 +98,17 @@ pub struct MirAnalyzer {
     basic_blocks: Vec<MirBasicBlock>,
     fn_id: DefId,
     name: String,
+    span: Range,
+    closure: Option<MirClosure>,
+    fields: Vec<MirFields>,
+    loans: Vec<MirLoan>,
+    conflicts: Vec<MirConflict>,
+    lifetimes: Vec<MirLifetime>,
     file_hash: String,
     mir_hash: String,
     accurate_live: HashMap<LocalId, Vec<Range>>,
     must_live: HashMap<LocalId, Vec<Range>>,
-    shared_live: HashMap<LocalId, Vec<Range>>,
-    mutable_live: HashMap<LocalId, Vec<Range>>,
+    field_borrow_live: HashMap<LocalId, Vec<MirFieldBorrow>>,
     drop_range: HashMap<LocalId, Vec<Range>>,
     storage_range: HashMap<LocalId, Vec<Range>>,
     definitely_live_range: HashMap<LocalId, Vec<Range>>,
@@ -50,16 +120,78 @@ impl MirAnalyzer {
         let mut result = HashMap::new();
 
         let facts = tcx.get_borrowck_facts(fn_id);
+        // nested bodies are only present if there are closures
+        let links: HashMap<_, _> = if 1 < facts.len() {
+            facts
+                .iter()
+                .map(|(fn_id, facts)| {
+                    let body = facts.body();
+                    let links = BodyLinks {
+                        variables: body.user_variable_locals(),
+                        holders: body.closure_holders(),
+                    };
+                    (*fn_id, links)
+                })
+                .collect()
+        } else {
+            HashMap::new()
+        };
         for (fn_id, mut facts) in facts {
-            let source_info = if let Some(v) = tcx.source_info_from_span(facts.body().span()) {
-                v
-            } else {
+            let name = tcx.def_name(fn_id);
+            let body = facts.body();
+            let located = tcx.source_info_from_span(body.span()).and_then(|v| {
+                let span = body.range(&v)?;
+                Some((v, span))
+            });
+            let Some((source_info, span)) = located else {
+                // the body is expanded from a macro whose source is not available,
+                // which is reported at the macro call site
+                let callsite = tcx
+                    .source_info_from_span(body.span().source_callsite())
+                    .and_then(|v| {
+                        let span = body.range(&v)?;
+                        Some((v, span))
+                    })
+                    .or_else(|| tcx.fallback_location(fn_id));
+                let Some((source_info, span)) = callsite else {
+                    log::warn!("{fn_id:?} ({name}) has no source in the crate");
+                    continue;
+                };
+                if !EXCLUSION.is_excluded(source_info.path(), &name) {
+                    log::debug!("{fn_id:?} ({name}) has no source; skip analysis");
+                    let skipped = SkipResult {
+                        file_path: source_info.path().to_path_buf(),
+                        span,
+                        fn_id: fn_id.as_u32(),
+                        name,
+                        reason: SkipReason::NoSource,
+                    };
+                    result.insert(fn_id, MirAnalyzerInitResult::Skipped(skipped));
+                }
                 continue;
             };
-            let name = tcx.def_name(fn_id);
+            if EXCLUSION.is_excluded(source_info.path(), &name) {
+                log::debug!("{fn_id:?} ({name}) is excluded");
+                continue;
+            }
             log::debug!("facts of {fn_id:?} ({name}) prepared; start analyze...");
 
-            let body = facts.body();
+            let skipped = |reason| SkipResult {
+                file_path: source_info.path().to_path_buf(),
+                span,
+                fn_id: fn_id.as_u32(),
+                name: name.clone(),
+                reason,
+            };
+            if body.is_tainted_by_errors() {
+                log::debug!("{fn_id:?} ({name}) has errors; skip analysis");
+                result.insert(
+                    fn_id,
+                    MirAnalyzerInitResult::Skipped(skipped(SkipReason::CompileError)),
+                );
+                continue;
+            }
+            let on_failure = skipped(SkipReason::AnalysisFailed);
 
             // collect local declared vars
             // this must be done in local thread
@@ -86,12 +218,14 @@ impl MirAnalyzer {
                 log::debug!("MIR cache hit: {fn_id:?}");
                 result.insert(
                     fn_id,
-                    MirAnalyzerInitResult::Cached(AnalyzeResult {
+                    MirAnalyzerInitResult::Cached(Box::new(AnalyzeResult {
                         file_path: source_info.path().to_path_buf(),
                         file_hash,
                         mir_hash,
+                        span,
                         analyzed: analyzed.clone(),
-                    }),
+                        cached: true,
+                    })),
                 );
                 continue;
             }
@@ -101,6 +235,10 @@ impl MirAnalyzer {
             // this must be done in local thread
             let user_vars = body.collect_user_variables(&source_info);
 
+            // link captured places to the locals of the enclosing functions
+            let closure = closure_link(tcx, fn_id, &source_info, &links);
+            let fields = body.collect_fields(fn_id);
+
             // build a Location -> source range map directly from the MIR body.
             let location_ranges = body.get_location_ranges(&source_info);
 
@@ -116,6 +254,10 @@ impl MirAnalyzer {
             // collect borrow data
             // this must be done in local thread
             let borrow_data = facts.borrow_map();
+            let mut loans = facts.collect_loans(fn_id, &location_ranges);
+
+            // named lifetime parameters and the locals which must outlive them
+            let lifetime_regions = facts.lifetime_regions(tcx, fn_id, &source_info, &region_vids);
 
             let input = facts.polonius_input();
             let location_table = facts.location_table();
@@ -125,6 +267,8 @@ impl MirAnalyzer {
                 // compute accurate region, which may eliminate invalid region
                 let output = input.compute();
                 log::debug!("second borrow check finished");
+                // the memory budget is checked between the steps of the analysis
+                budget::check_memory();
 
                 let accurate_live = polonius_analyzer::get_accurate_live(
                     &output,
@@ -139,7 +283,7 @@ impl MirAnalyzer {
                     &location_ranges,
                 );
 
-                let (shared_live, mutable_live) = polonius_analyzer::get_borrow_live(
+                let field_borrow_live = polonius_analyzer::get_field_borrow_live(
                     &output,
                     &location_table,
                     &borrow_data,
@@ -148,6 +292,53 @@ impl MirAnalyzer {
 
                 let drop_range =
                     polonius_analyzer::drop_range(&output, &location_table, &location_ranges);
+                budget::check_memory();
+
+                let mut explained = polonius_analyzer::explain_loans(
+                    &input,
+                    &output,
+                    &location_table,
+                    &location_ranges,
+                );
+                for loan in &mut loans {
+                    let mut chain = explained.remove(&loan.id).unwrap_or_default();
+                    // the creation of the borrow is shown by itself
+                    chain.retain(|v| Some(*v) != loan.range);
+                    loan.chain = chain;
+                }
+                let conflicts = polonius_analyzer::conflicts(
+                    &input,
+                    &output,
+                    &location_table,
+                    &location_ranges,
+                    &basic_blocks,
+                    &loans,
+                );
+                budget::check_memory();
+
+                let lifetimes = lifetime_regions
+                    .into_iter()
+                    .map(|lifetime| {
+                        let constrained = polonius_analyzer::reference_local_live_range(
+                            &output,
+                            lifetime.regions.into_iter(),
+                            &location_table,
+                            &location_ranges,
+                        )
+                        .into_iter()
+                        .filter(|(_, ranges)| !ranges.is_empty())
+                        .map(|(local, ranges)| MirConstrainedLocal {
+                            local: FnLocal::new(local.as_u32(), fn_id.as_u32()),
+                            ranges,
+                        })
+                        .collect();
+                        MirLifetime {
+                            name: lifetime.name,
+                            spans: lifetime.spans,
+                            constrained,
+                        }
+                    })
+                    .collect();
 
                 let reference_local_live = polonius_analyzer::reference_local_live_range(
                     &output,
@@ -155,6 +346,7 @@ impl MirAnalyzer {
                     &location_table,
                     &location_ranges,
                 );
+                budget::check_memory();
 
                 // CFG based liveness analysis
                 log::debug!("start CFG based liveness check");
@@ -189,19 +381,24 @@ impl MirAnalyzer {
                     basic_blocks: basic_blocks.values().cloned().collect(),
                     fn_id,
                     name,
+                    span,
+                    closure,
+                    fields,
+                    loans,
+                    conflicts,
+                    lifetimes,
                     file_hash,
                     mir_hash,
                     accurate_live,
                     must_live,
-                    shared_live,
-                    mutable_live,
+                    field_borrow_live,
                     drop_range,
                     storage_range,
                     definitely_live_range,
                     maybe_init_range,
                 }
             });
-            result.insert(fn_id, MirAnalyzerInitResult::Analyzer(analyzer));
+            result.insert(fn_id, MirAnalyzerInitResult::Analyzer(analyzer, on_failure));
         }
         result
     }
@@ -221,8 +418,11 @@ impl MirAnalyzer {
                 let ty = ty.clone();
                 let must_live_at = must_live_at.get(local).cloned().unwrap_or(Vec::new());
                 let lives = lives.get(local).cloned().unwrap_or(Vec::new());
-                let shared_borrow = self.shared_live.get(local).cloned().unwrap_or(Vec::new());
-                let mutable_borrow = self.mutable_live.get(local).cloned().unwrap_or(Vec::new());
+                let field_borrows = self
+                    .field_borrow_live
+                    .get(local)
+                    .cloned()
+                    .unwrap_or_default();
                 let drop = self.is_drop(*local);
                 let drop_range = drop_range.get(local).cloned().unwrap_or(Vec::new());
                 let storage_range = storage_range.get(local).cloned().unwrap_or(Vec::new());
@@ -247,8 +447,7 @@ impl MirAnalyzer {
                         span,
                         ty,
                         lives,
-                        shared_borrow,
-                        mutable_borrow,
+                        field_borrows,
                         must_live_at,
                         drop,
                         drop_range,
@@ -261,8 +460,7 @@ impl MirAnalyzer {
                         local: fn_local,
                         ty,
                         lives,
-                        shared_borrow,
-                        mutable_borrow,
+                        field_borrows,
                         drop,
                         drop_range,
                         must_live_at,
@@ -293,12 +491,19 @@ impl MirAnalyzer {
             file_path: self.file_path,
             file_hash: self.file_hash,
             mir_hash: self.mir_hash,
+            span: self.span,
             analyzed: Function {
                 fn_id: self.fn_id.as_u32(),
                 name: self.name,
                 basic_blocks,
                 decls,
+                closure: self.closure,
+                fields: self.fields,
+                loans: self.loans,
+                conflicts: self.conflicts,
+                lifetimes: self.lifetimes,
             },
+            cached: false,
         }
     }
 }
@@ -30,10 +30,15 @@ use std::collections::{HashMap, VecDeque};
 /// [`CfgAnalyzer::visit_terminator`]:
 ///
 /// - `Assign` to a local sets it to `Initialized`. If the rvalue is a
-///   `Move`, the source local is set to `Moved` first.
+///   `Move`, the source local is set to `Moved` first. Moving a field out of
+///   a local leaves the local's state unchanged, since its other fields are
+///   still usable.
 /// - `StorageDead` sets the local to `Uninitialized`.
 /// - A `Call` terminator sets each `Move` argument to `Moved` and the
 ///   destination local to `Initialized`.
+/// - A `Yield` terminator sets a `Move` yielded value to `Moved` and the
+///   resume argument to `Initialized`. Both the resume and the drop edges
+///   are followed, so states flow across suspension points.
 /// - A `Drop` terminator removes `Initialized` and adds `Dropped`. Other
 ///   variants (e.g. an earlier `Moved`) survive so that joins keep
 ///   reflecting all paths reaching the location.
@@ -125,6 +130,7 @@ impl CfgAnalyzer {
 
     pub fn visit_operand(&mut self, operand: &MirOperand, location: Location) {
         if let MirOperand::Move { place, .. } = operand
+            && !place.is_field()
             && let Some(local_states) = self.states.get_mut(&location)
             && let Some(state) =
                 local_states
@@ -142,7 +148,8 @@ impl CfgAnalyzer {
             MirRval::Use { operand }
             | MirRval::Repeat { operand }
             | MirRval::Cast { operand }
-            | MirRval::UnaryOp { operand } => {
+            | MirRval::UnaryOp { operand }
+            | MirRval::WrapUnsafeBinder { operand } => {
                 self.visit_operand(operand, location);
             }
             MirRval::BinaryOp { left, right } => {
@@ -154,7 +161,12 @@ impl CfgAnalyzer {
                     self.visit_operand(field, location);
                 }
             }
-            MirRval::Ref { .. } | MirRval::Other => {}
+            MirRval::Ref { .. }
+            | MirRval::ThreadLocalRef
+            | MirRval::RawPtr { .. }
+            | MirRval::PtrMetadata { .. }
+            | MirRval::Discriminant { .. }
+            | MirRval::CopyForDeref { .. } => {}
         }
     }
     pub fn visit_statement(&mut self, statement: &MirStatement, location: Location) {
@@ -180,7 +192,13 @@ impl CfgAnalyzer {
                     state.insert(LocalStateVariant::Uninitialized);
                 }
             }
-            _ => {}
+            // reads without moving, and a write to an initialized place
+            MirStatementKind::FakeRead { .. }
+            | MirStatementKind::PlaceMention { .. }
+            | MirStatementKind::SetDiscriminant { .. }
+            | MirStatementKind::StorageLive { .. }
+            | MirStatementKind::Nop
+            | MirStatementKind::Other => {}
         }
     }
     pub fn visit_terminator(&mut self, terminator: &MirTerminator, location: Location) {
@@ -226,6 +244,20 @@ impl CfgAnalyzer {
             MirTerminatorKind::Assert { cond, .. } => {
                 self.visit_operand(cond, location);
             }
+            // the yielded value leaves the coroutine, and the resumed value is stored on resume
+            MirTerminatorKind::Yield {
+                value, resume_arg, ..
+            } => {
+                self.visit_operand(value, location);
+                if let Some(local_states) = self.states.get_mut(&location)
+                    && let Some(state) = local_states.0.get_mut(&LocalId::from_rustc(
+                        rustc_middle::mir::Local::from_u32(resume_arg.local.id),
+                    ))
+                {
+                    state.clear();
+                    state.insert(LocalStateVariant::Initialized);
+                }
+            }
 
             _ => {}
         }
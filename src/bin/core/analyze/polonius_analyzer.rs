@@ -1,6 +1,6 @@
 use rayon::prelude::*;
 use rustowl::{models::*, utils};
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use super::*;
 
@@ -19,56 +19,57 @@ pub fn get_accurate_live(
     )
 }
 
-/// returns (shared, mutable)
-pub fn get_borrow_live(
+/// Live ranges of the borrows of each local by the borrowed field path and the mutability
+pub fn get_field_borrow_live(
     datafrog: &PoloniusOutput,
     location_table: &PoloniusLocationTable,
     borrow_map: &BorrowMap,
     location_ranges: &LocationRanges,
-) -> (HashMap<LocalId, Vec<Range>>, HashMap<LocalId, Vec<Range>>) {
-    let output = datafrog;
-    let mut shared_borrows = HashMap::new();
-    let mut mutable_borrows = HashMap::new();
-    for (location_idx, borrow_idc) in output.loan_live_at().iter() {
+) -> HashMap<LocalId, Vec<MirFieldBorrow>> {
+    let mut borrows: HashMap<_, HashMap<_, Vec<_>>> = HashMap::new();
+    for (location_idx, borrow_idc) in datafrog.loan_live_at().iter() {
         let location = location_table.get_rich_location(location_idx);
         for borrow_idx in borrow_idc {
-            match borrow_map.get_from_borrow(borrow_idx) {
-                Some((_, BorrowData::Shared { borrowed, .. })) => {
-                    shared_borrows
-                        .entry(*borrowed)
-                        .or_insert_with(Vec::new)
-                        .push(location);
-                }
-                Some((_, BorrowData::Mutable { borrowed, .. })) => {
-                    mutable_borrows
-                        .entry(*borrowed)
-                        .or_insert_with(Vec::new)
-                        .push(location);
-                }
-                _ => {}
-            }
+            let (borrowed, fields, mutable) = match borrow_map.get_from_borrow(borrow_idx) {
+                Some((
+                    _,
+                    BorrowData::Shared {
+                        borrowed, fields, ..
+                    },
+                )) => (borrowed, fields, false),
+                Some((
+                    _,
+                    BorrowData::Mutable {
+                        borrowed, fields, ..
+                    },
+                )) => (borrowed, fields, true),
+                None => continue,
+            };
+            borrows
+                .entry(*borrowed)
+                .or_default()
+                .entry((fields.clone(), mutable))
+                .or_default()
+                .push(location);
         }
     }
-    (
-        shared_borrows
-            .into_par_iter()
-            .map(|(local, locations)| {
-                (
-                    local,
-                    utils::eliminated_ranges(rich_locations_to_ranges(location_ranges, &locations)),
-                )
-            })
-            .collect(),
-        mutable_borrows
-            .into_par_iter()
-            .map(|(local, locations)| {
-                (
-                    local,
-                    utils::eliminated_ranges(rich_locations_to_ranges(location_ranges, &locations)),
-                )
-            })
-            .collect(),
-    )
+    borrows
+        .into_par_iter()
+        .map(|(local, borrows)| {
+            let borrows = borrows
+                .into_iter()
+                .map(|((fields, mutable), locations)| MirFieldBorrow {
+                    fields,
+                    mutable,
+                    ranges: utils::eliminated_ranges(rich_locations_to_ranges(
+                        location_ranges,
+                        &locations,
+                    )),
+                })
+                .collect();
+            (local, borrows)
+        })
+        .collect()
 }
 
 pub fn get_must_live(
@@ -168,6 +169,305 @@ pub fn get_must_live(
     }))
 }
 
+/// Why each borrow lives this long, by the borrow index
+///
+/// The borrow is followed along the control flow from where it is issued, through the points
+/// where it is live, and the use reached last keeps it live. The result is the ranges of the
+/// region constraints at those points which the borrow flows through, from the region it is
+/// issued into to the region of that use, followed by the range of the use.
+pub fn explain_loans(
+    input: &PoloniusInput,
+    output: &PoloniusOutput,
+    location_table: &PoloniusLocationTable,
+    location_ranges: &LocationRanges,
+) -> HashMap<u32, Vec<Range>> {
+    let range_of = |point: &Point| {
+        let location = match location_table.get_rich_location(point) {
+            RichLocation::Start(l) | RichLocation::Mid(l) => l,
+        };
+        location_ranges.get(&location).copied()
+    };
+    let contains = output.origin_contains_loan_at();
+    let live = output.loan_live_at();
+
+    let mut successors: HashMap<Point, Vec<Point>> = HashMap::new();
+    for (from, to) in input.cfg_edge() {
+        successors.entry(from).or_default().push(to);
+    }
+    // obtain a map that point -> region constraints added there; the subsets computed by
+    // polonius are transitive, which would skip the references in between
+    let mut flows: HashMap<Point, Vec<(Region, Region)>> = HashMap::new();
+    for (sub, sup, point) in input.subset_base() {
+        flows.entry(point).or_default().push((sub, sup));
+    }
+
+    let mut derefs: HashMap<LocalId, Vec<Region>> = HashMap::new();
+    for (local, region) in input.use_of_var_derefs_origin() {
+        derefs.entry(local).or_default().push(region);
+    }
+    let mut used_at: HashMap<Point, Vec<LocalId>> = HashMap::new();
+    for (local, point) in input.var_used_at() {
+        used_at.entry(point).or_default().push(local);
+    }
+
+    let mut result = HashMap::new();
+    for (issued, borrow, issued_at) in input.loan_issued_at() {
+        let holds = |point: &Point, region: &Region| {
+            contains
+                .get(point)
+                .and_then(|regions| regions.get(region))
+                .is_some_and(|borrows| borrows.contains(&borrow))
+        };
+
+        // points where the borrow is live, in the order they are reached from where it is issued
+        let mut reached = vec![issued_at];
+        let mut visited = HashSet::from([issued_at]);
+        let mut next = 0;
+        while let Some(point) = reached.get(next).copied() {
+            next += 1;
+            for succ in successors.get(&point).into_iter().flatten() {
+                if live.get(succ).is_some_and(|v| v.contains(&borrow)) && visited.insert(*succ) {
+                    reached.push(*succ);
+                }
+            }
+        }
+
+        // the use reached last, e.g. in the next iteration of a loop, keeps the borrow live
+        let use_site = reached.iter().rev().find_map(|point| {
+            let region = used_at.get(point)?.iter().find_map(|local| {
+                derefs
+                    .get(local)?
+                    .iter()
+                    .find(|region| holds(point, region))
+            })?;
+            Some((range_of(point)?, *region))
+        });
+        let Some((use_range, use_region)) = use_site else {
+            continue;
+        };
+
+        // the earliest constraint comes first so that the chain follows the control flow
+        let mut flows_into: HashMap<Region, Vec<(Region, Option<Range>)>> = HashMap::new();
+        for point in &reached {
+            for (sub, sup) in flows.get(point).into_iter().flatten() {
+                flows_into
+                    .entry(*sub)
+                    .or_default()
+                    .push((*sup, range_of(point)));
+            }
+        }
+
+        // breadth first search from the issuing region to the region of the use
+        let mut previous = HashMap::new();
+        let mut visited = HashSet::from([issued]);
+        let mut queue = VecDeque::from([issued]);
+        while let Some(region) = queue.pop_front() {
+            if region == use_region {
+                break;
+            }
+            for (sup, range) in flows_into.get(&region).into_iter().flatten() {
+                if visited.insert(*sup) {
+                    previous.insert(*sup, (region, *range));
+                    queue.push_back(*sup);
+                }
+            }
+        }
+
+        let mut chain = Vec::new();
+        let mut region = use_region;
+        while let Some((prev, range)) = previous.get(&region) {
+            chain.extend(range);
+            region = *prev;
+        }
+        chain.reverse();
+        // a statement may add several constraints on the way
+        let mut seen = HashSet::from([use_range]);
+        chain.retain(|range| seen.insert(*range));
+        chain.push(use_range);
+        result.insert(borrow.as_u32(), chain);
+    }
+    result
+}
+
+/// How the statement or terminator at the location accesses the local
+fn access_kind(block: &MirBasicBlock, statement: usize, local: FnLocal) -> MirAccessKind {
+    let moves = |operand: &MirOperand| matches!(operand, MirOperand::Move { place } if place.local == local);
+    match block.statements.get(statement).map(|v| &v.kind) {
+        Some(MirStatementKind::Assign { place, rval }) => match rval {
+            MirRval::Ref {
+                place: borrowed,
+                mutable,
+            }
+            | MirRval::RawPtr {
+                place: borrowed,
+                mutable,
+            } if borrowed.local == local => {
+                if *mutable {
+                    MirAccessKind::MutableBorrow
+                } else {
+                    MirAccessKind::SharedBorrow
+                }
+            }
+            MirRval::Use { operand }
+            | MirRval::Repeat { operand }
+            | MirRval::Cast { operand }
+            | MirRval::UnaryOp { operand }
+            | MirRval::WrapUnsafeBinder { operand }
+                if moves(operand) =>
+            {
+                MirAccessKind::Move
+            }
+            MirRval::BinaryOp { left, right } if moves(left) || moves(right) => MirAccessKind::Move,
+            MirRval::Aggregate { fields } if fields.iter().any(moves) => MirAccessKind::Move,
+            _ if place.local == local => MirAccessKind::Assign,
+            _ => MirAccessKind::Read,
+        },
+        Some(MirStatementKind::StorageDead { .. }) => MirAccessKind::Drop,
+        Some(_) => MirAccessKind::Read,
+        None => match &block.terminator.kind {
+            MirTerminatorKind::Call {
+                args, destination, ..
+            } => {
+                if args.iter().any(moves) {
+                    MirAccessKind::Move
+                } else if destination.local == local {
+                    MirAccessKind::Assign
+                } else {
+                    MirAccessKind::Read
+                }
+            }
+            MirTerminatorKind::Drop { .. } => MirAccessKind::Drop,
+            _ => MirAccessKind::Read,
+        },
+    }
+}
+
+/// Accesses to borrowed places while the borrows are live
+pub fn conflicts(
+    input: &PoloniusInput,
+    output: &PoloniusOutput,
+    location_table: &PoloniusLocationTable,
+    location_ranges: &LocationRanges,
+    basic_blocks: &IndexMap<BasicBlockId, MirBasicBlock>,
+    loans: &[MirLoan],
+) -> Vec<MirConflict> {
+    let range_of = |point: &Point| {
+        let location = match location_table.get_rich_location(point) {
+            RichLocation::Start(l) | RichLocation::Mid(l) => l,
+        };
+        location_ranges.get(&location).copied()
+    };
+    let contains = output.origin_contains_loan_at();
+    let live_vars = output.var_live_on_entry();
+    let mut successors: HashMap<Point, Vec<Point>> = HashMap::new();
+    for (from, to) in input.cfg_edge() {
+        successors.entry(from).or_default().push(to);
+    }
+    let mut derefs: HashMap<LocalId, Vec<Region>> = HashMap::new();
+    for (local, region) in input.use_of_var_derefs_origin() {
+        derefs.entry(local).or_default().push(region);
+    }
+    let mut used_at: HashMap<Point, Vec<LocalId>> = HashMap::new();
+    for (local, point) in input.var_used_at() {
+        used_at.entry(point).or_default().push(local);
+    }
+
+    let mut result: Vec<MirConflict> = Vec::new();
+    for (point, borrows) in output.errors() {
+        let location = match location_table.get_rich_location(&point) {
+            RichLocation::Start(l) | RichLocation::Mid(l) => l,
+        };
+        let Some(range) = location_ranges.get(&location).copied() else {
+            continue;
+        };
+        let Some(block) = basic_blocks.get(&BasicBlockId(location.block() as usize)) else {
+            continue;
+        };
+        for borrow in borrows {
+            let Some(loan) = loans.get(borrow.as_u32() as usize) else {
+                continue;
+            };
+            // the borrow may be killed by the access itself, e.g. an assignment to the place,
+            // so the references holding it are looked up at the access
+            let holds = |local: &LocalId| {
+                derefs.get(local).into_iter().flatten().any(|region| {
+                    contains
+                        .get(&point)
+                        .and_then(|v| v.get(region))
+                        .is_some_and(|v| v.contains(&borrow))
+                })
+            };
+            let holders: Vec<_> = derefs.keys().filter(|local| holds(local)).collect();
+            let held = |locals: Option<&Vec<LocalId>>| {
+                locals.into_iter().flatten().any(|v| holders.contains(&v))
+            };
+            let used = first_use(
+                point,
+                &successors,
+                |point| held(live_vars.get(point)),
+                |point| held(used_at.get(point)) && range_of(point).is_some_and(|v| v != range),
+            )
+            .and_then(|point| range_of(&point));
+            result.push(MirConflict {
+                loan: loan.id,
+                access: access_kind(block, location.statement() as usize, loan.borrowed.local),
+                range,
+                used,
+            });
+        }
+    }
+    merge_conflicts(result)
+}
+
+/// The point reached first along the control flow from `start` where the references
+/// holding a borrow are used, following the points where they are live
+///
+/// The use may be earlier in the source than `start`, e.g. in the next iteration of a loop.
+fn first_use<P: Copy + Eq + std::hash::Hash>(
+    start: P,
+    successors: &HashMap<P, Vec<P>>,
+    live: impl Fn(&P) -> bool,
+    used: impl Fn(&P) -> bool,
+) -> Option<P> {
+    let mut visited = HashSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(point) = queue.pop_front() {
+        for succ in successors.get(&point).into_iter().flatten() {
+            if !live(succ) || !visited.insert(*succ) {
+                continue;
+            }
+            if used(succ) {
+                return Some(*succ);
+            }
+            queue.push_back(*succ);
+        }
+    }
+    None
+}
+
+/// Merge the conflicts of a borrow at overlapping ranges
+///
+/// A two-phase borrow, e.g. `v.push(..)`, conflicts both where it is created and activated.
+fn merge_conflicts(mut result: Vec<MirConflict>) -> Vec<MirConflict> {
+    result.sort_by_key(|v| (v.loan, v.range.from(), std::cmp::Reverse(v.range.until())));
+    let mut merged: Vec<MirConflict> = Vec::new();
+    for conflict in result {
+        if let Some(last) = merged.last_mut()
+            && last.loan == conflict.loan
+            && utils::common_range(last.range, conflict.range).is_some()
+        {
+            if last.access == MirAccessKind::Read {
+                last.access = conflict.access;
+            }
+            last.used = last.used.or(conflict.used);
+            continue;
+        }
+        merged.push(conflict);
+    }
+    merged.sort_by_key(|v| (v.range.from(), v.loan));
+    merged
+}
+
 /// obtain map from local id to living range
 pub fn drop_range(
     datafrog: &PoloniusOutput,
@@ -242,3 +542,154 @@ pub fn get_range(
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(id: u32) -> FnLocal {
+        FnLocal::new(id, 0)
+    }
+
+    fn place(id: u32) -> MirPlace {
+        MirPlace {
+            local: local(id),
+            projection: Vec::new(),
+        }
+    }
+
+    fn range(from: u32, until: u32) -> Range {
+        Range::new(Loc(from), Loc(until)).unwrap()
+    }
+
+    fn block(rvals: Vec<(u32, MirRval)>, terminator: MirTerminatorKind) -> MirBasicBlock {
+        MirBasicBlock {
+            statements: rvals
+                .into_iter()
+                .map(|(to, rval)| MirStatement {
+                    kind: MirStatementKind::Assign {
+                        place: place(to),
+                        rval,
+                    },
+                    range: None,
+                })
+                .collect(),
+            terminator: MirTerminator {
+                kind: terminator,
+                range: None,
+            },
+        }
+    }
+
+    #[test]
+    fn access_kinds() {
+        // _1 = &mut _0; _2 = &_0; _3 = move _0; _0 = copy _4; _5 = copy _0; f(move _0)
+        let block = block(
+            vec![
+                (
+                    1,
+                    MirRval::Ref {
+                        place: place(0),
+                        mutable: true,
+                    },
+                ),
+                (
+                    2,
+                    MirRval::Ref {
+                        place: place(0),
+                        mutable: false,
+                    },
+                ),
+                (
+                    3,
+                    MirRval::Use {
+                        operand: MirOperand::Move { place: place(0) },
+                    },
+                ),
+                (
+                    0,
+                    MirRval::Use {
+                        operand: MirOperand::Copy { place: place(4) },
+                    },
+                ),
+                (
+                    5,
+                    MirRval::Use {
+                        operand: MirOperand::Copy { place: place(0) },
+                    },
+                ),
+            ],
+            MirTerminatorKind::Call {
+                func: MirOperand::Constant,
+                args: vec![MirOperand::Move { place: place(0) }],
+                destination: place(6),
+                target: None,
+                fn_range: None,
+            },
+        );
+        let kinds: Vec<_> = (0..=block.statements.len())
+            .map(|statement| access_kind(&block, statement, local(0)))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                MirAccessKind::MutableBorrow,
+                MirAccessKind::SharedBorrow,
+                MirAccessKind::Move,
+                MirAccessKind::Assign,
+                MirAccessKind::Read,
+                MirAccessKind::Move,
+            ]
+        );
+    }
+
+    #[test]
+    fn first_use_follows_loop() {
+        // 0: loop header, 1: use of the reference, 2: conflicting access, 3: after the loop
+        let successors = HashMap::from([(0, vec![1, 3]), (1, vec![2]), (2, vec![0])]);
+        let used = |point: &u32| *point == 1;
+        assert_eq!(first_use(2, &successors, |v| *v != 3, used), Some(1));
+        // the reference is not live in the next iteration
+        assert_eq!(first_use(2, &successors, |v| *v != 0, used), None);
+    }
+
+    #[test]
+    fn two_phase_borrow_conflicts_merged() {
+        // `v.push(v.len())` conflicts where `&mut v` is created and where it is activated
+        let conflict = |loan, access, range, used| MirConflict {
+            loan,
+            access,
+            range,
+            used,
+        };
+        let merged = merge_conflicts(vec![
+            conflict(0, MirAccessKind::Read, range(10, 20), None),
+            conflict(1, MirAccessKind::Move, range(40, 45), Some(range(50, 55))),
+            conflict(
+                0,
+                MirAccessKind::MutableBorrow,
+                range(12, 15),
+                Some(range(30, 35)),
+            ),
+        ]);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(
+            (
+                merged[0].loan,
+                merged[0].access,
+                merged[0].range,
+                merged[0].used
+            ),
+            (
+                0,
+                MirAccessKind::MutableBorrow,
+                range(10, 20),
+                Some(range(30, 35))
+            )
+        );
+        assert_eq!(
+            (merged[1].loan, merged[1].access, merged[1].range),
+            (1, MirAccessKind::Move, range(40, 45))
+        );
+    }
+}
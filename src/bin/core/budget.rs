@@ -0,0 +1,275 @@
+use super::analyze::SkipResult;
+use rustowl::budget::{BUDGET_EXCEEDED_CODE, Budget};
+use rustowl::models::SkipReason;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+pub static BUDGET: LazyLock<Budget> = LazyLock::new(Budget::from_env);
+
+/// Name of the currently-compiling crate, set before the analysis starts
+pub static CRATE_NAME: OnceLock<String> = OnceLock::new();
+
+/// Functions under analysis, reported as skipped when the crate is aborted
+static PENDING: LazyLock<Mutex<HashMap<u32, SkipResult>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub fn start_function(on_failure: &SkipResult) {
+    PENDING
+        .lock()
+        .unwrap()
+        .insert(on_failure.fn_id, on_failure.clone());
+}
+
+pub fn finish_function(fn_id: u32) {
+    PENDING.lock().unwrap().remove(&fn_id);
+}
+
+/// Memory of the analysis of a function, which allocates from an arena of jemalloc
+/// of its own, so that memory freed on other threads is also taken into account
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+mod arena {
+    use std::ffi::{CStr, CString, c_void};
+    use std::sync::Mutex;
+
+    /// Arenas of finished analyses, which are reused by later ones
+    static ARENAS: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+    pub const SUPPORTED: bool = true;
+
+    fn read<T: Default>(name: &CStr) -> Option<T> {
+        let mut value = T::default();
+        let mut len = size_of::<T>();
+        let status = unsafe {
+            tikv_jemalloc_sys::mallctl(
+                name.as_ptr(),
+                (&raw mut value).cast::<c_void>(),
+                &mut len,
+                std::ptr::null_mut(),
+                0,
+            )
+        };
+        (status == 0).then_some(value)
+    }
+
+    /// Write the value and return the previous one
+    fn replace<T: Default>(name: &CStr, mut value: T) -> Option<T> {
+        let mut old = T::default();
+        let mut len = size_of::<T>();
+        let status = unsafe {
+            tikv_jemalloc_sys::mallctl(
+                name.as_ptr(),
+                (&raw mut old).cast::<c_void>(),
+                &mut len,
+                (&raw mut value).cast::<c_void>(),
+                size_of::<T>(),
+            )
+        };
+        (status == 0).then_some(old)
+    }
+
+    /// Return the blocks cached by the thread to their arenas
+    fn flush_tcache() {
+        unsafe {
+            tikv_jemalloc_sys::mallctl(
+                c"thread.tcache.flush".as_ptr(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                0,
+            );
+        }
+    }
+
+    /// Arena bound to the thread while the analysis runs on it
+    pub struct Arena {
+        index: u32,
+        previous: u32,
+        /// Bytes held by the arena when the analysis started,
+        /// which may be freed by the results of earlier analyses
+        baseline: usize,
+        limit: usize,
+    }
+
+    impl Arena {
+        pub fn bind(limit: usize) -> Option<Self> {
+            let reused = ARENAS.lock().unwrap().pop();
+            let index = reused.or_else(|| read::<u32>(c"arenas.create"))?;
+            let previous = replace(c"thread.arena", index)?;
+            // cached blocks belong to the previous arena
+            flush_tcache();
+            Some(Self {
+                index,
+                previous,
+                baseline: allocated(index)?,
+                limit,
+            })
+        }
+
+        pub fn exceeded(&self) -> bool {
+            allocated(self.index).is_some_and(|v| self.limit < v.saturating_sub(self.baseline))
+        }
+    }
+
+    impl Drop for Arena {
+        fn drop(&mut self) {
+            replace(c"thread.arena", self.previous);
+            flush_tcache();
+            ARENAS.lock().unwrap().push(self.index);
+        }
+    }
+
+    /// Bytes allocated from the arena
+    fn allocated(index: u32) -> Option<usize> {
+        // statistics are updated only when the epoch advances
+        replace(c"epoch", 1u64)?;
+        let stat = |kind| {
+            let name = CString::new(format!("stats.arenas.{index}.{kind}.allocated")).ok()?;
+            read::<usize>(&name)
+        };
+        Some(stat("small")? + stat("large")?)
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+mod arena {
+    pub const SUPPORTED: bool = false;
+
+    pub struct Arena;
+
+    impl Arena {
+        pub fn bind(_limit: usize) -> Option<Self> {
+            None
+        }
+
+        pub fn exceeded(&self) -> bool {
+            false
+        }
+    }
+}
+
+thread_local! {
+    /// Arena of the function analyzed on the thread
+    static ARENA: RefCell<Option<arena::Arena>> = const { RefCell::new(None) };
+}
+
+/// Panic payload of the analysis of a function which exceeded the memory budget
+pub struct MemoryBudgetExceeded;
+
+/// Abort the analysis of the function on the thread if it has exceeded the memory budget,
+/// which frees the memory held by the analysis
+///
+/// This is called between the steps of the analysis, not on each allocation.
+pub fn check_memory() {
+    let exceeded = ARENA.with_borrow(|arena| arena.as_ref().is_some_and(|v| v.exceeded()));
+    if exceeded {
+        std::panic::panic_any(MemoryBudgetExceeded);
+    }
+}
+
+/// Unbinds the arena from the thread when the analysis finishes or panics
+struct MemoryBudgetGuard;
+impl Drop for MemoryBudgetGuard {
+    fn drop(&mut self) {
+        ARENA.take();
+    }
+}
+
+/// Run the analysis of a function within the memory budget of a function,
+/// which panics with [`MemoryBudgetExceeded`] at [`check_memory`] when exceeded
+pub fn with_memory_budget<R>(f: impl FnOnce() -> R) -> R {
+    let _guard = MemoryBudgetGuard;
+    if let Some(limit) = BUDGET.function_memory_limit_bytes() {
+        let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+        ARENA.set(arena::Arena::bind(limit));
+    }
+    f()
+}
+
+/// Resident memory of this process in bytes
+#[cfg(target_os = "linux")]
+fn resident_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|v| v.starts_with("VmRSS:"))?;
+    let kib: u64 = line
+        .trim_start_matches("VmRSS:")
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kib * 1024)
+}
+#[cfg(not(target_os = "linux"))]
+fn resident_memory() -> Option<u64> {
+    None
+}
+
+/// Watch the crate budget and abort the process when it is exceeded
+///
+/// `json` specifies whether the error is reported in the JSON diagnostic format.
+pub fn start_watchdog(json: bool) {
+    let budget = *BUDGET;
+    if budget.function_memory_limit.is_some() && !arena::SUPPORTED {
+        log::warn!("memory budget of functions is not supported on this platform");
+    }
+    if budget.crate_timeout.is_none() && budget.crate_memory_limit.is_none() {
+        return;
+    }
+    if budget.crate_memory_limit.is_some() && resident_memory().is_none() {
+        log::warn!("memory budget is not supported on this platform");
+    }
+    let started = Instant::now();
+    std::thread::spawn(move || {
+        loop {
+            if let Some(timeout) = budget.crate_timeout()
+                && timeout <= started.elapsed()
+            {
+                abort(
+                    SkipReason::TimedOut,
+                    &format!("exceeded the time budget of {}s", timeout.as_secs()),
+                    json,
+                );
+            }
+            if let Some(limit) = budget.crate_memory_limit_bytes()
+                && let Some(used) = resident_memory()
+                && limit <= used
+            {
+                abort(
+                    SkipReason::MemoryLimitExceeded,
+                    &format!("exceeded the memory budget of {} MiB", limit / 1024 / 1024),
+                    json,
+                );
+            }
+            std::thread::sleep(Duration::from_millis(200));
+        }
+    });
+}
+
+fn abort(reason: SkipReason, exceeded: &str, json: bool) -> ! {
+    let message = if let Some(crate_name) = CRATE_NAME.get() {
+        let pending = std::mem::take(&mut *PENDING.lock().unwrap());
+        for (_, skipped) in pending {
+            super::handle_skipped_result(crate_name, SkipResult { reason, ..skipped });
+        }
+        format!("analysis of crate `{crate_name}` {exceeded}")
+    } else {
+        format!("analysis {exceeded}")
+    };
+    if json {
+        let diagnostic = serde_json::json!({
+            "$message_type": "diagnostic",
+            "message": message,
+            "code": { "code": BUDGET_EXCEEDED_CODE, "explanation": null },
+            "level": "error",
+            "spans": [],
+            "children": [],
+            "rendered": format!("error: {message}\n"),
+        });
+        eprintln!("{diagnostic}");
+    } else {
+        eprintln!("error: {message}");
+    }
+    std::process::exit(1);
+}
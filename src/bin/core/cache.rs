@@ -30,10 +30,10 @@ impl CacheData {
 /// Get cache data
 ///
 /// If cache is not enabled, then return None.
-/// If file is not exists, it returns empty [`CacheData`].
+/// If file is not exists or cannot be parsed, it returns empty [`CacheData`].
 pub fn get_cache(krate: &str) -> Option<CacheData> {
-    if let Some(cache_path) = rustowl::cache::get_cache_path() {
-        let cache_path = cache_path.join(format!("{krate}.json"));
+    if let Some(cache_dir) = rustowl::cache::get_cache_path() {
+        let cache_path = rustowl::cache::cache_file(&cache_dir, krate);
         let s = match std::fs::read_to_string(&cache_path) {
             Ok(v) => v,
             Err(e) => {
@@ -41,21 +41,29 @@ pub fn get_cache(krate: &str) -> Option<CacheData> {
                 return Some(CacheData::default());
             }
         };
-        let read = serde_json::from_str(&s).ok();
         log::debug!("cache read: {}", cache_path.display());
-        read
+        match serde_json::from_str(&s) {
+            Ok(v) => Some(v),
+            // written by another version of RustOwl; overwritten by this run
+            Err(e) => {
+                log::warn!("failed to parse incremental cache file: {e}");
+                Some(CacheData::default())
+            }
+        }
     } else {
         None
     }
 }
 
 pub fn write_cache(krate: &str, cache: &CacheData) {
-    if let Some(cache_path) = rustowl::cache::get_cache_path() {
-        if let Err(e) = std::fs::create_dir_all(&cache_path) {
+    if let Some(cache_dir) = rustowl::cache::get_cache_path() {
+        let cache_path = rustowl::cache::cache_file(&cache_dir, krate);
+        if let Some(dir) = cache_path.parent()
+            && let Err(e) = std::fs::create_dir_all(dir)
+        {
             log::warn!("failed to create cache dir: {e}");
             return;
         }
-        let cache_path = cache_path.join(format!("{krate}.json"));
         let s = serde_json::to_string(cache).unwrap();
         let mut f = match std::fs::OpenOptions::new()
             .write(true)
@@ -75,3 +83,24 @@ pub fn write_cache(krate: &str, cache: &CacheData) {
         log::debug!("incremental cache saved: {}", cache_path.display());
     }
 }
+
+/// Write analysis results of the currently-compiling unit
+///
+/// The file name is the same as the metadata file emitted by the compiler,
+/// so that it can be found from cargo's artifact messages.
+pub fn write_result(unit_name: &str, ws: &Workspace) {
+    if let Some(result_dir) = rustowl::cache::get_result_path() {
+        if let Err(e) = std::fs::create_dir_all(&result_dir) {
+            log::warn!("failed to create result dir: {e}");
+            return;
+        }
+        let Some(result_path) = rustowl::cache::result_file(&result_dir, unit_name) else {
+            return;
+        };
+        let s = serde_json::to_string(ws).unwrap();
+        if let Err(e) = std::fs::write(&result_path, s) {
+            log::warn!("failed to write analysis result: {e}");
+        }
+        log::debug!("analysis result saved: {}", result_path.display());
+    }
+}
@@ -159,6 +159,22 @@ impl<'tcx> TyCtxt<'tcx> {
         })
     }
 
+    /// Where a function whose body has no source is reported: the source of the item,
+    /// or the start of the crate root when the item has no source either
+    pub fn fallback_location(&self, def_id: DefId) -> Option<(SourceInfo, Range)> {
+        let tcx = *self.as_rustc();
+        let span = Span::from_rustc(tcx.def_span(*def_id.as_rustc()).source_callsite());
+        if let Some(source_info) = self.source_info_from_span(span)
+            && let Some(range) = range_from_span(&source_info.source, span, source_info.offset)
+        {
+            return Some((source_info, range));
+        }
+        let root = tcx.def_span(rustc_hir::def_id::CRATE_DEF_ID);
+        let source_info = self.source_info_from_span(Span::from_rustc(root))?;
+        let range = Range::new(Loc(0), Loc(1))?;
+        Some((source_info, range))
+    }
+
     pub fn crate_name(&self) -> String {
         self.as_rustc()
             .crate_name(rustc_hir::def_id::LOCAL_CRATE)
@@ -168,6 +184,111 @@ impl<'tcx> TyCtxt<'tcx> {
     pub fn def_name(&self, def_id: DefId) -> String {
         self.as_rustc().def_path_str(def_id.as_rustc().to_def_id())
     }
+
+    /// Enclosing body of a closure or coroutine
+    pub fn closure_parent(&self, def_id: DefId) -> Option<DefId> {
+        let tcx = self.as_rustc();
+        tcx.is_closure_like(def_id.as_rustc().to_def_id())
+            .then(|| AsRustc::from_rustc(tcx.local_parent(*def_id.as_rustc())))
+    }
+
+    /// Where each named lifetime parameter is declared and used in the signature of the function
+    pub fn lifetime_spans(
+        &self,
+        def_id: DefId,
+        source_info: &SourceInfo,
+    ) -> IndexMap<String, Vec<Range>> {
+        use rustc_hir::{GenericParamKind, LifetimeKind, LifetimeParamKind, intravisit::*};
+
+        struct LifetimeVisitor {
+            spans: Vec<(String, rustc_span::Span)>,
+        }
+        impl<'v> Visitor<'v> for LifetimeVisitor {
+            fn visit_lifetime(&mut self, lifetime: &'v rustc_hir::Lifetime) {
+                if let LifetimeKind::Param(_) = lifetime.kind
+                    && !lifetime.is_anonymous()
+                    && !lifetime.is_implicit()
+                {
+                    self.spans
+                        .push((lifetime.ident.to_string(), lifetime.ident.span));
+                }
+            }
+        }
+
+        let tcx = *self.as_rustc();
+        let def_id = *def_id.as_rustc();
+        let mut visitor = LifetimeVisitor { spans: Vec::new() };
+        for param in tcx
+            .hir_get_generics(def_id)
+            .into_iter()
+            .flat_map(|v| v.params)
+        {
+            if let GenericParamKind::Lifetime {
+                kind: LifetimeParamKind::Explicit,
+            } = param.kind
+            {
+                let ident = param.name.ident();
+                visitor.spans.push((ident.to_string(), ident.span));
+            }
+        }
+        if let Some(decl) = tcx.hir_fn_decl_by_hir_id(tcx.local_def_id_to_hir_id(def_id)) {
+            walk_fn_decl(&mut visitor, decl);
+        }
+
+        let mut result: IndexMap<String, Vec<Range>> = IndexMap::new();
+        for (name, span) in visitor.spans {
+            if let Some(range) = range_from_span(
+                &source_info.source,
+                AsRustc::from_rustc(span),
+                source_info.offset,
+            ) {
+                result.entry(name).or_default().push(range);
+            }
+        }
+        result
+    }
+
+    /// Places captured by a closure or coroutine
+    ///
+    /// `find_local` resolves the binding span of the captured variable to its local;
+    /// captures of unresolved variables are omitted.
+    pub fn closure_captures(
+        &self,
+        def_id: DefId,
+        source_info: &SourceInfo,
+        find_local: impl Fn(Span) -> Option<FnLocal>,
+    ) -> Vec<MirCapture> {
+        use rustc_middle::ty::{BorrowKind, UpvarCapture};
+
+        let tcx = *self.as_rustc();
+        tcx.closure_captures(*def_id.as_rustc())
+            .iter()
+            .filter_map(|captured| {
+                let local = find_local(AsRustc::from_rustc(
+                    tcx.hir_span(captured.get_root_variable()),
+                ))?;
+                let range = range_from_span(
+                    &source_info.source,
+                    AsRustc::from_rustc(captured.get_capture_kind_span(tcx)),
+                    source_info.offset,
+                )?;
+                let kind = match captured.info.capture_kind {
+                    // `use` closures clone the place, which is then owned by the closure
+                    UpvarCapture::ByValue | UpvarCapture::ByUse => MirCaptureKind::ByMove,
+                    UpvarCapture::ByRef(BorrowKind::Immutable) => MirCaptureKind::ByRef,
+                    UpvarCapture::ByRef(BorrowKind::UniqueImmutable | BorrowKind::Mutable) => {
+                        MirCaptureKind::ByMutRef
+                    }
+                };
+                Some(MirCapture {
+                    local,
+                    place: captured.to_string(tcx),
+                    kind,
+                    range,
+                })
+            })
+            .collect()
+    }
 }
 
 impl_as_rustc!(
@@ -190,6 +311,60 @@ impl<'tcx> Body<'tcx> {
             .collect()
     }
 
+    /// Region variables of the arguments and the return place, with the name of the lifetime
+    /// parameter at the same position in the signature
+    pub fn signature_region_vids(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        def_id: DefId,
+    ) -> Vec<(String, LocalId, RegionVid)> {
+        use rustc_hir::def::DefKind;
+        use rustc_middle::mir::{Local, RETURN_PLACE};
+        use rustc_middle::ty::RegionKind;
+
+        let tcx = *tcx.as_rustc();
+        let def_id = def_id.as_rustc().to_def_id();
+        if !matches!(tcx.def_kind(def_id), DefKind::Fn | DefKind::AssocFn) {
+            return Vec::new();
+        }
+        let sig =
+            tcx.liberate_late_bound_regions(def_id, tcx.fn_sig(def_id).instantiate_identity());
+        let places = std::iter::once((RETURN_PLACE, sig.output())).chain(
+            sig.inputs()
+                .iter()
+                .enumerate()
+                .map(|(i, ty)| (Local::from_usize(i + 1), *ty)),
+        );
+
+        let mut result = Vec::new();
+        for (local, sig_ty) in places {
+            let Some(decl) = self.0.local_decls.get(local) else {
+                continue;
+            };
+            let mut sig_regions = Vec::new();
+            tcx.for_each_free_region(&sig_ty, |r| sig_regions.push(r));
+            let mut regions = Vec::new();
+            tcx.for_each_free_region(&decl.ty, |r| regions.push(r));
+            // the types differ if the signature is not normalized, e.g. an associated type
+            if sig_regions.len() != regions.len() {
+                continue;
+            }
+            for (sig_region, region) in sig_regions.into_iter().zip(regions) {
+                if let Some(name) = sig_region.get_name(tcx)
+                    && !sig_region.is_static()
+                    && let RegionKind::ReVar(vid) = region.kind()
+                {
+                    result.push((
+                        name.to_string(),
+                        LocalId::from_rustc(local),
+                        RegionVid::from_rustc(vid),
+                    ));
+                }
+            }
+        }
+        result
+    }
+
     pub fn get_local_region_vids(&self) -> IndexMap<LocalId, RegionVid> {
         self.as_rustc()
             .local_decls
@@ -225,10 +400,128 @@ impl<'tcx> Body<'tcx> {
             .collect()
     }
 
+    /// Locals of user variables by the span of their binding
+    pub fn user_variable_locals(&self) -> HashMap<Span, LocalId> {
+        self.0
+            .var_debug_info
+            .iter()
+            .filter_map(|debug| match &debug.value {
+                rustc_middle::mir::VarDebugInfoContents::Place(place)
+                    if place.projection.is_empty() =>
+                {
+                    Some((
+                        AsRustc::from_rustc(debug.source_info.span),
+                        AsRustc::from_rustc(place.local),
+                    ))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Field names of the struct and tuple places whose fields are used in this body
+    pub fn collect_fields(&self, fn_id: DefId) -> Vec<MirFields> {
+        use rustc_middle::mir::{visit::*, *};
+        use rustc_middle::ty::TyKind;
+
+        struct FieldVisitor<'a, 'tcx> {
+            local_decls: &'a LocalDecls<'tcx>,
+            fields: IndexMap<(Local, Vec<usize>), Vec<String>>,
+        }
+        impl<'tcx> Visitor<'tcx> for FieldVisitor<'_, 'tcx> {
+            fn visit_place(&mut self, place: &Place<'tcx>, _: PlaceContext, _: Location) {
+                let mut ty = self.local_decls[place.local].ty;
+                let mut path = Vec::new();
+                for elem in place.projection {
+                    let ProjectionElem::Field(idx, field_ty) = elem else {
+                        break;
+                    };
+                    let names = match ty.kind() {
+                        TyKind::Adt(def, _) if def.is_struct() => def
+                            .non_enum_variant()
+                            .fields
+                            .iter()
+                            .map(|v| v.name.to_string())
+                            .collect(),
+                        TyKind::Tuple(tys) => (0..tys.len()).map(|v| v.to_string()).collect(),
+                        _ => break,
+                    };
+                    self.fields
+                        .entry((place.local, path.clone()))
+                        .or_insert(names);
+                    path.push(idx.as_usize());
+                    ty = field_ty;
+                }
+            }
+        }
+
+        let mut visitor = FieldVisitor {
+            local_decls: &self.0.local_decls,
+            fields: IndexMap::new(),
+        };
+        visitor.visit_body(&self.0);
+        visitor
+            .fields
+            .into_iter()
+            .map(|((local, path), names)| MirFields {
+                local: FnLocal::new(local.as_u32(), fn_id.as_u32()),
+                path,
+                names,
+            })
+            .collect()
+    }
+
+    /// Locals holding the closures and coroutines defined in this body
+    pub fn closure_holders(&self) -> HashMap<DefId, LocalId> {
+        use rustc_middle::mir::*;
+
+        self.0
+            .basic_blocks
+            .iter()
+            .flat_map(|bb| &bb.statements)
+            .filter_map(|stmt| {
+                let StatementKind::Assign(assign) = &stmt.kind else {
+                    return None;
+                };
+                let (place, Rvalue::Aggregate(kind, _)) = &**assign else {
+                    return None;
+                };
+                match **kind {
+                    AggregateKind::Closure(def_id, _)
+                    | AggregateKind::Coroutine(def_id, _)
+                    | AggregateKind::CoroutineClosure(def_id, _)
+                        if place.projection.is_empty() =>
+                    {
+                        def_id.as_local().map(|def_id| {
+                            (
+                                AsRustc::from_rustc(def_id),
+                                AsRustc::from_rustc(place.local),
+                            )
+                        })
+                    }
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
     pub fn span(&self) -> Span {
         AsRustc::from_rustc(self.0.span)
     }
 
+    /// Source range of the whole body
+    pub fn range(&self, source_info: &SourceInfo) -> Option<Range> {
+        range_from_span(
+            &source_info.source,
+            AsRustc::from_rustc(self.0.span.source_callsite()),
+            source_info.offset,
+        )
+    }
+
+    pub fn is_tainted_by_errors(&self) -> bool {
+        self.0.tainted_by_errors.is_some()
+    }
+
     pub fn get_location_ranges(&self, source_info: &SourceInfo) -> LocationRanges {
         LocationRanges::compute(self, source_info)
     }
@@ -359,6 +652,10 @@ impl Span {
         use rustc_span::Pos;
         self.0.hi().to_u32()
     }
+    /// The outermost macro call site of the span
+    pub fn source_callsite(&self) -> Span {
+        Span(self.0.source_callsite())
+    }
 }
 
 impl_as_rustc!(
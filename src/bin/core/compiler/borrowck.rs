@@ -14,6 +14,12 @@ impl_as_rustc!(
     <rustc_borrowck::consumers::RustcFacts as polonius_engine::FactTypes>::Loan,
 );
 
+impl Borrow {
+    pub fn as_u32(&self) -> u32 {
+        self.as_rustc().as_u32()
+    }
+}
+
 impl_as_rustc!(
     #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
     Region,
@@ -43,6 +49,53 @@ impl PoloniusInput {
             .map(|(v, p)| (AsRustc::from_rustc(*v), AsRustc::from_rustc(*p)))
             .collect()
     }
+    pub fn var_used_at(&self) -> Vec<(LocalId, Point)> {
+        self.as_rustc()
+            .var_used_at
+            .iter()
+            .map(|(v, p)| (AsRustc::from_rustc(*v), AsRustc::from_rustc(*p)))
+            .collect()
+    }
+    pub fn use_of_var_derefs_origin(&self) -> Vec<(LocalId, Region)> {
+        self.as_rustc()
+            .use_of_var_derefs_origin
+            .iter()
+            .map(|(v, r)| (AsRustc::from_rustc(*v), AsRustc::from_rustc(*r)))
+            .collect()
+    }
+    pub fn subset_base(&self) -> Vec<(Region, Region, Point)> {
+        self.as_rustc()
+            .subset_base
+            .iter()
+            .map(|(r1, r2, p)| {
+                (
+                    AsRustc::from_rustc(*r1),
+                    AsRustc::from_rustc(*r2),
+                    AsRustc::from_rustc(*p),
+                )
+            })
+            .collect()
+    }
+    pub fn cfg_edge(&self) -> Vec<(Point, Point)> {
+        self.as_rustc()
+            .cfg_edge
+            .iter()
+            .map(|(p, q)| (AsRustc::from_rustc(*p), AsRustc::from_rustc(*q)))
+            .collect()
+    }
+    pub fn loan_issued_at(&self) -> Vec<(Region, Borrow, Point)> {
+        self.as_rustc()
+            .loan_issued_at
+            .iter()
+            .map(|(r, b, p)| {
+                (
+                    AsRustc::from_rustc(*r),
+                    AsRustc::from_rustc(*b),
+                    AsRustc::from_rustc(*p),
+                )
+            })
+            .collect()
+    }
 }
 
 impl_as_rustc!(PoloniusOutput, rustc_borrowck::consumers::PoloniusOutput);
@@ -115,6 +168,19 @@ impl PoloniusOutput {
             })
             .collect()
     }
+    /// Borrows invalidated while they are live, i.e. borrow-check errors
+    pub fn errors(&self) -> HashMap<Point, Vec<Borrow>> {
+        self.as_rustc()
+            .errors
+            .iter()
+            .map(|(p, bs)| {
+                (
+                    AsRustc::from_rustc(*p),
+                    bs.iter().map(|b| AsRustc::from_rustc(*b)).collect(),
+                )
+            })
+            .collect()
+    }
     pub fn origin_contains_loan_at(&self) -> HashMap<Point, HashMap<Region, HashSet<Borrow>>> {
         self.as_rustc()
             .origin_contains_loan_at
@@ -159,6 +225,13 @@ impl<'tcx> BorrowckFacts<'tcx> {
         BorrowMap::new(&self.as_rustc().borrow_set)
     }
 
+    /// Whether the region outlives the other after region inference
+    pub fn outlives(&self, sup: RegionVid, sub: RegionVid) -> bool {
+        self.as_rustc()
+            .region_inference_context
+            .eval_outlives(sup.into_rustc(), sub.into_rustc())
+    }
+
     pub fn polonius_input(&mut self) -> PoloniusInput {
         AsRustc::from_rustc(*self.mut_rustc().input_facts.take().unwrap())
     }
@@ -81,10 +81,14 @@ impl<'tcx> TyCtxt<'tcx> {
 pub enum BorrowData {
     Shared {
         borrowed: LocalId,
+        /// Field path of the borrowed place
+        fields: Vec<usize>,
         assigned: LocalId,
     },
     Mutable {
         borrowed: LocalId,
+        /// Field path of the borrowed place
+        fields: Vec<usize>,
         assigned: LocalId,
     },
 }
@@ -228,14 +232,17 @@ impl BorrowMap {
         let mut location_map = HashMap::new();
         // BorrowIndex corresponds to Location index
         for (location, data) in borrow_set.location_map().iter() {
+            let fields = Place::from_rustc(data.borrowed_place()).fields();
             let data = if data.kind().mutability().is_mut() {
                 BorrowData::Mutable {
                     borrowed: AsRustc::from_rustc(data.borrowed_place().local),
+                    fields,
                     assigned: AsRustc::from_rustc(data.assigned_place().local),
                 }
             } else {
                 BorrowData::Shared {
                     borrowed: AsRustc::from_rustc(data.borrowed_place().local),
+                    fields,
                     assigned: AsRustc::from_rustc(data.assigned_place().local),
                 }
             };
@@ -271,6 +278,72 @@ impl BorrowMap {
     }
 }
 
+/// A named lifetime parameter with the regions of the locals constrained by it
+#[derive(Clone, Debug)]
+pub struct LifetimeRegions {
+    pub name: String,
+    pub spans: Vec<Range>,
+    pub regions: Vec<(LocalId, RegionVid)>,
+}
+
+impl<'tcx> BorrowckFacts<'tcx> {
+    /// Named lifetime parameters of the function, with the arguments whose types use them and
+    /// the reference locals which must outlive them
+    pub fn lifetime_regions(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        fn_id: DefId,
+        source_info: &SourceInfo,
+        region_vids: &IndexMap<LocalId, RegionVid>,
+    ) -> Vec<LifetimeRegions> {
+        let signature = self.body().signature_region_vids(tcx, fn_id);
+        tcx.lifetime_spans(fn_id, source_info)
+            .into_iter()
+            .filter_map(|(name, spans)| {
+                let mut regions: Vec<_> = signature
+                    .iter()
+                    .filter(|(v, _, _)| *v == name)
+                    .map(|(_, local, vid)| (*local, *vid))
+                    .collect();
+                let params: Vec<_> = regions.iter().map(|(_, vid)| *vid).collect();
+                for (local, vid) in region_vids {
+                    if regions.iter().all(|(v, _)| v != local)
+                        && params.iter().any(|param| self.outlives(*vid, *param))
+                    {
+                        regions.push((*local, *vid));
+                    }
+                }
+                (!regions.is_empty()).then_some(LifetimeRegions {
+                    name,
+                    spans,
+                    regions,
+                })
+            })
+            .collect()
+    }
+
+    /// Collect the borrows of the body into our data structure [`MirLoan`]s.
+    pub fn collect_loans(&self, fn_id: DefId, location_ranges: &LocationRanges) -> Vec<MirLoan> {
+        self.as_rustc()
+            .borrow_set
+            .location_map()
+            .iter()
+            .enumerate()
+            .map(|(id, (location, data))| MirLoan {
+                // BorrowIndex corresponds to the index in location_map
+                id: id as u32,
+                borrowed: Place::from_rustc(data.borrowed_place()).transform(fn_id),
+                holder: FnLocal::new(data.assigned_place().local.as_u32(), fn_id.as_u32()),
+                mutable: data.kind().mutability().is_mut(),
+                range: location_ranges
+                    .get(&Location::from_rustc(*location))
+                    .copied(),
+                chain: Vec::new(),
+            })
+            .collect()
+    }
+}
+
 impl_as_rustc!(
     #[derive(Clone, Hash, Debug)]
     Place<'tcx>,
@@ -297,6 +370,19 @@ impl Place<'_> {
             .collect();
         MirPlace { local, projection }
     }
+
+    /// Path of the fields projected from the local
+    pub fn fields(&self) -> Vec<usize> {
+        use rustc_middle::mir::ProjectionElem;
+        self.as_rustc()
+            .projection
+            .iter()
+            .map_while(|e| match e {
+                ProjectionElem::Field(idx, _ty) => Some(idx.as_usize()),
+                _ => None,
+            })
+            .collect()
+    }
 }
 
 impl_as_rustc!(
@@ -314,7 +400,8 @@ impl Operand<'_> {
             Operand::Move(place) => MirOperand::Move {
                 place: Place::from_rustc(*place).transform(fn_id),
             },
-            _ => MirOperand::Other,
+            Operand::Constant(_) => MirOperand::Constant,
+            Operand::RuntimeChecks(_) => MirOperand::RuntimeChecks,
         }
     }
 }
@@ -326,7 +413,7 @@ impl_as_rustc!(
 );
 impl Rvalue<'_> {
     pub fn transform(&self, fn_id: DefId) -> MirRval {
-        use rustc_middle::mir::Rvalue;
+        use rustc_middle::mir::{RawPtrKind, Rvalue};
         match &self.as_rustc() {
             Rvalue::Use(operand) => {
                 let operand = Operand::from_rustc(operand.clone()).transform(fn_id);
@@ -361,7 +448,31 @@ impl Rvalue<'_> {
                     .collect();
                 MirRval::Aggregate { fields }
             }
-            _ => MirRval::Other,
+            Rvalue::ThreadLocalRef(_) => MirRval::ThreadLocalRef,
+            Rvalue::RawPtr(kind, place) => {
+                let place = Place::from_rustc(*place).transform(fn_id);
+                match kind {
+                    RawPtrKind::Mut => MirRval::RawPtr {
+                        place,
+                        mutable: true,
+                    },
+                    RawPtrKind::Const => MirRval::RawPtr {
+                        place,
+                        mutable: false,
+                    },
+                    RawPtrKind::FakeForPtrMetadata => MirRval::PtrMetadata { place },
+                }
+            }
+            Rvalue::Discriminant(place) => MirRval::Discriminant {
+                place: Place::from_rustc(*place).transform(fn_id),
+            },
+            Rvalue::CopyForDeref(place) => MirRval::CopyForDeref {
+                place: Place::from_rustc(*place).transform(fn_id),
+            },
+            Rvalue::WrapUnsafeBinder(operand, _ty) => {
+                let operand = Operand::from_rustc(operand.clone()).transform(fn_id);
+                MirRval::WrapUnsafeBinder { operand }
+            }
         }
     }
 }
@@ -379,7 +490,7 @@ impl Statement<'_> {
         statement_index: usize,
         location_ranges: &LocationRanges,
     ) -> MirStatement {
-        use rustc_middle::mir::StatementKind;
+        use rustc_middle::mir::{FakeReadCause, StatementKind};
         let location = rustc_middle::mir::Location {
             block: rustc_middle::mir::BasicBlock::from_usize(block.0),
             statement_index,
@@ -406,6 +517,32 @@ impl Statement<'_> {
                 },
                 range,
             },
+            StatementKind::FakeRead(boxed) => {
+                let cause = match (**boxed).0 {
+                    FakeReadCause::ForMatchedPlace(_) => MirFakeReadCause::MatchedPlace,
+                    FakeReadCause::ForMatchGuard => MirFakeReadCause::MatchGuard,
+                    FakeReadCause::ForGuardBinding => MirFakeReadCause::GuardBinding,
+                    FakeReadCause::ForLet(_) => MirFakeReadCause::Let,
+                    FakeReadCause::ForIndex => MirFakeReadCause::Index,
+                };
+                let place = Place::from_rustc((**boxed).1).transform(fn_id);
+                MirStatement {
+                    kind: MirStatementKind::FakeRead { place, cause },
+                    range,
+                }
+            }
+            StatementKind::SetDiscriminant { place, .. } => MirStatement {
+                kind: MirStatementKind::SetDiscriminant {
+                    place: Place::from_rustc(**place).transform(fn_id),
+                },
+                range,
+            },
+            StatementKind::PlaceMention(place) => MirStatement {
+                kind: MirStatementKind::PlaceMention {
+                    place: Place::from_rustc(**place).transform(fn_id),
+                },
+                range,
+            },
             StatementKind::Nop => MirStatement {
                 kind: MirStatementKind::Nop,
                 range,
@@ -534,17 +671,54 @@ impl Terminator<'_> {
                     range,
                 }
             }
-            _ => {
-                let successors = self
-                    .as_rustc()
-                    .successors()
-                    .map(|v| BasicBlockId(v.as_usize()))
-                    .collect();
-                MirTerminator {
-                    kind: MirTerminatorKind::Other { successors },
-                    range,
-                }
+            TerminatorKind::Yield {
+                value,
+                resume,
+                resume_arg,
+                drop,
+            } => {
+                let kind = MirTerminatorKind::Yield {
+                    value: Operand::from_rustc(value.clone()).transform(fn_id),
+                    resume: BasicBlockId(resume.as_usize()),
+                    resume_arg: Place::from_rustc(*resume_arg).transform(fn_id),
+                    drop: drop.map(|v| BasicBlockId(v.as_usize())),
+                };
+                MirTerminator { kind, range }
             }
+            TerminatorKind::CoroutineDrop => MirTerminator {
+                kind: MirTerminatorKind::CoroutineDrop,
+                range,
+            },
+            TerminatorKind::UnwindResume => MirTerminator {
+                kind: MirTerminatorKind::UnwindResume,
+                range,
+            },
+            TerminatorKind::UnwindTerminate(_) => MirTerminator {
+                kind: MirTerminatorKind::UnwindTerminate,
+                range,
+            },
+            TerminatorKind::FalseEdge {
+                real_target,
+                imaginary_target,
+            } => MirTerminator {
+                kind: MirTerminatorKind::FalseEdge {
+                    real_target: BasicBlockId(real_target.as_usize()),
+                    imaginary_target: BasicBlockId(imaginary_target.as_usize()),
+                },
+                range,
+            },
+            TerminatorKind::FalseUnwind { real_target, .. } => MirTerminator {
+                kind: MirTerminatorKind::FalseUnwind {
+                    real_target: BasicBlockId(real_target.as_usize()),
+                },
+                range,
+            },
+            TerminatorKind::InlineAsm { targets, .. } => MirTerminator {
+                kind: MirTerminatorKind::InlineAsm {
+                    targets: targets.iter().map(|v| BasicBlockId(v.as_usize())).collect(),
+                },
+                range,
+            },
         }
     }
 }
@@ -1,20 +1,25 @@
 pub mod analyze;
+pub mod budget;
 pub mod cache;
 pub mod compiler;
 
-use analyze::{AnalyzeResult, MirAnalyzer, MirAnalyzerInitResult};
+use analyze::{AnalyzeResult, MirAnalyzeFuture, MirAnalyzer, MirAnalyzerInitResult, SkipResult};
 use compiler::AsRustc;
+use rustc_data_structures::stable_hasher::StableHasher;
 use rustc_hir::def_id::{LOCAL_CRATE, LocalDefId};
 use rustc_interface::interface;
 use rustc_middle::{ty::TyCtxt, util::Providers};
-use rustc_session::config;
+use rustc_session::{Session, config};
+use rustc_span::Symbol;
 use rustowl::models::*;
 use std::collections::HashMap;
 use std::env;
+use std::path::Path;
 use std::process::ExitCode;
 use std::sync::{LazyLock, Mutex, atomic::AtomicBool};
 use tokio::{
     runtime::{Builder, Runtime},
+    sync::Semaphore,
     task::JoinSet,
 };
 
@@ -27,21 +32,28 @@ pub struct RustcCallback;
 impl rustc_driver::Callbacks for RustcCallback {}
 
 static ATOMIC_TRUE: AtomicBool = AtomicBool::new(true);
-static TASKS: LazyLock<Mutex<JoinSet<AnalyzeResult>>> =
+/// All results of the currently-compiling unit, stored for replay
+static RESULTS: LazyLock<Mutex<Workspace>> = LazyLock::new(|| Mutex::new(Workspace::default()));
+static TASKS: LazyLock<Mutex<JoinSet<Result<AnalyzeResult, SkipResult>>>> =
     LazyLock::new(|| Mutex::new(JoinSet::new()));
+static WORKER_THREADS: LazyLock<usize> = LazyLock::new(|| {
+    std::thread::available_parallelism()
+        .map(|n| (n.get() / 2).clamp(2, 8))
+        .unwrap_or(4)
+});
 // make tokio runtime
 static RUNTIME: LazyLock<Runtime> = LazyLock::new(|| {
-    let worker_threads = std::thread::available_parallelism()
-        .map(|n| (n.get() / 2).clamp(2, 8))
-        .unwrap_or(4);
-
     Builder::new_multi_thread()
         .enable_all()
-        .worker_threads(worker_threads)
+        .worker_threads(*WORKER_THREADS)
         .thread_stack_size(128 * 1024 * 1024)
         .build()
         .unwrap()
 });
+/// Limits the number of functions analyzed at once to the number of workers
+static ANALYSIS_SLOTS: LazyLock<Semaphore> = LazyLock::new(|| Semaphore::new(*WORKER_THREADS));
+/// Limits the number of timed-out analyses left running without an analysis slot
+static ABANDONED_SLOTS: LazyLock<Semaphore> = LazyLock::new(|| Semaphore::new(*WORKER_THREADS));
 
 static DEFAULT_MIR_BORROWCK: LazyLock<
     fn(TyCtxt<'_>, LocalDefId) -> queries::mir_borrowck::ProvidedValue<'_>,
@@ -63,16 +75,29 @@ fn mir_borrowck(tcx: TyCtxt<'_>, def_id: LocalDefId) -> queries::mir_borrowck::P
     log::debug!("start borrowck of {def_id:?}");
 
     let default_borrowck_result = DEFAULT_MIR_BORROWCK(tcx, def_id);
+    let crate_name = tcx.crate_name(LOCAL_CRATE).to_string();
     let analyzers = MirAnalyzer::init(AsRustc::from_rustc(tcx), AsRustc::from_rustc(def_id));
     {
         let mut tasks = TASKS.lock().unwrap();
         for (_, analyzer) in analyzers {
             match analyzer {
                 MirAnalyzerInitResult::Cached(cached) => {
-                    handle_analyzed_result(tcx, cached);
+                    handle_analyzed_result(&crate_name, *cached);
                 }
-                MirAnalyzerInitResult::Analyzer(analyzer) => {
-                    tasks.spawn_on(async move { analyzer.await.analyze() }, RUNTIME.handle());
+                MirAnalyzerInitResult::Analyzer(analyzer, on_failure) => {
+                    budget::start_function(&on_failure);
+                    tasks.spawn_on(
+                        async move {
+                            let fn_id = on_failure.fn_id;
+                            let result = run_analyzer(analyzer, on_failure).await;
+                            budget::finish_function(fn_id);
+                            result
+                        },
+                        RUNTIME.handle(),
+                    );
+                }
+                MirAnalyzerInitResult::Skipped(skipped) => {
+                    handle_skipped_result(&crate_name, skipped);
                 }
             }
         }
@@ -80,13 +105,109 @@ fn mir_borrowck(tcx: TyCtxt<'_>, def_id: LocalDefId) -> queries::mir_borrowck::P
         log::debug!("there are {} tasks", tasks.len());
         while let Some(Ok(result)) = tasks.try_join_next() {
             log::debug!("one task joined");
-            handle_analyzed_result(tcx, result);
+            handle_task_result(&crate_name, result);
         }
     }
 
     default_borrowck_result
 }
 
+/// Run the analysis on a blocking thread, which is abandoned
+/// when the analysis exceeds the time budget
+///
+/// The time budget includes waiting for a slot, so that a crate finishes in time
+/// even if slots are held by abandoned analyses.
+async fn run_analyzer(
+    analyzer: MirAnalyzeFuture,
+    on_failure: SkipResult,
+) -> Result<AnalyzeResult, SkipResult> {
+    let deadline = budget::BUDGET
+        .function_timeout()
+        .map(|timeout| tokio::time::Instant::now() + timeout);
+    let Some(permit) = until(deadline, ANALYSIS_SLOTS.acquire()).await else {
+        log::warn!(
+            "analysis of {} timed out waiting for a slot",
+            on_failure.name
+        );
+        return Err(SkipResult {
+            reason: SkipReason::TimedOut,
+            ..on_failure
+        });
+    };
+    let permit = permit.unwrap();
+    // a separate task also catches panics during analysis
+    let mut task = tokio::task::spawn_blocking(move || {
+        budget::with_memory_budget(|| RUNTIME.handle().block_on(analyzer).analyze())
+    });
+    let result = until(deadline, &mut task).await.ok_or(task);
+    match result {
+        Ok(Ok(analyzed)) => Ok(analyzed),
+        Ok(Err(e)) => match e.try_into_panic() {
+            Ok(payload) if payload.is::<budget::MemoryBudgetExceeded>() => {
+                log::warn!("analysis of {} exceeded the memory budget", on_failure.name);
+                Err(SkipResult {
+                    reason: SkipReason::MemoryLimitExceeded,
+                    ..on_failure
+                })
+            }
+            Ok(_) => {
+                log::warn!("analysis of {} panicked", on_failure.name);
+                Err(on_failure)
+            }
+            Err(e) => {
+                log::warn!("analysis of {} failed: {e}", on_failure.name);
+                Err(on_failure)
+            }
+        },
+        Err(task) => {
+            log::warn!("analysis of {} timed out", on_failure.name);
+            // the thread cannot be stopped, so it keeps a slot for abandoned analyses
+            // until it finishes, or the analysis slot when there are too many of them
+            match ABANDONED_SLOTS.try_acquire() {
+                Ok(abandoned) => {
+                    drop(permit);
+                    RUNTIME.spawn(async move {
+                        let _abandoned = abandoned;
+                        task.await.ok();
+                    });
+                }
+                Err(_) => {
+                    RUNTIME.spawn(async move {
+                        let _permit = permit;
+                        task.await.ok();
+                    });
+                }
+            }
+            Err(SkipResult {
+                reason: SkipReason::TimedOut,
+                ..on_failure
+            })
+        }
+    }
+}
+
+/// Run the future until the deadline, if any
+async fn until<F: Future>(deadline: Option<tokio::time::Instant>, future: F) -> Option<F::Output> {
+    if let Some(deadline) = deadline {
+        tokio::time::timeout_at(deadline, future).await.ok()
+    } else {
+        Some(future.await)
+    }
+}
+
+/// Record the settings passed by environment variables in the dep-info,
+/// since results are replayed until cargo considers the unit dirty
+fn track_env(sess: &Session, _hasher: &mut StableHasher) {
+    let mut env_depinfo = sess.env_depinfo.borrow_mut();
+    for key in rustowl::budget::ENV_VARS
+        .into_iter()
+        .chain(rustowl::config::ENV_VARS)
+    {
+        let value = env::var(key).ok();
+        env_depinfo.insert((Symbol::intern(key), value.as_deref().map(Symbol::intern)));
+    }
+}
+
 pub struct AnalyzerCallback;
 impl rustc_driver::Callbacks for AnalyzerCallback {
     fn config(&mut self, config: &mut interface::Config) {
@@ -96,12 +217,15 @@ impl rustc_driver::Callbacks for AnalyzerCallback {
         config.opts.incremental = None;
         config.override_queries = Some(override_queries);
         config.make_codegen_backend = None;
+        config.track_state = Some(Box::new(track_env));
     }
     fn after_expansion<'tcx>(
         &mut self,
         _compiler: &interface::Compiler,
         tcx: TyCtxt<'tcx>,
     ) -> rustc_driver::Compilation {
+        let crate_name = tcx.crate_name(LOCAL_CRATE).to_string();
+        let _ = budget::CRATE_NAME.set(crate_name.clone());
         let result = rustc_driver::catch_fatal_errors(|| tcx.analysis(()));
 
         // join all tasks after all analysis finished
@@ -112,12 +236,19 @@ impl rustc_driver::Callbacks for AnalyzerCallback {
         RUNTIME.block_on(async move {
             while let Some(Ok(result)) = { TASKS.lock().unwrap().join_next().await } {
                 log::debug!("one task joined");
-                handle_analyzed_result(tcx, result);
+                handle_task_result(&crate_name, result);
             }
             if let Some(cache) = cache::CACHE.lock().unwrap().as_ref() {
-                cache::write_cache(&tcx.crate_name(LOCAL_CRATE).to_string(), cache);
+                cache::write_cache(&crate_name, cache);
             }
         });
+        // the same name as the metadata file, e.g. `libfoo-0123456789abcdef.rmeta`
+        let unit_name = format!(
+            "lib{}{}.rmeta",
+            tcx.crate_name(LOCAL_CRATE),
+            tcx.sess.opts.cg.extra_filename
+        );
+        cache::write_result(&unit_name, &RESULTS.lock().unwrap());
 
         if result.is_ok() {
             rustc_driver::Compilation::Continue
@@ -127,7 +258,14 @@ impl rustc_driver::Callbacks for AnalyzerCallback {
     }
 }
 
-pub fn handle_analyzed_result(tcx: TyCtxt<'_>, analyzed: AnalyzeResult) {
+fn handle_task_result(crate_name: &str, result: Result<AnalyzeResult, SkipResult>) {
+    match result {
+        Ok(analyzed) => handle_analyzed_result(crate_name, analyzed),
+        Err(skipped) => handle_skipped_result(crate_name, skipped),
+    }
+}
+
+pub fn handle_analyzed_result(crate_name: &str, analyzed: AnalyzeResult) {
     if let Some(cache) = cache::CACHE.lock().unwrap().as_mut() {
         cache.insert_cache(
             analyzed.file_hash.clone(),
@@ -135,16 +273,27 @@ pub fn handle_analyzed_result(tcx: TyCtxt<'_>, analyzed: AnalyzeResult) {
             analyzed.analyzed.clone(),
         );
     }
+    let item = Item::Function {
+        span: analyzed.span,
+        mir: analyzed.analyzed,
+        cached: analyzed.cached,
+    };
+    print_item(crate_name, &analyzed.file_path, item);
+}
+
+pub fn handle_skipped_result(crate_name: &str, skipped: SkipResult) {
+    let file_path = skipped.file_path.clone();
+    print_item(crate_name, &file_path, skipped.item());
+}
+
+fn print_item(crate_name: &str, file_path: &Path, item: Item) {
     let krate = Crate(HashMap::from([(
-        analyzed.file_path.to_string_lossy().to_string(),
-        File {
-            items: vec![analyzed.analyzed],
-        },
+        file_path.to_string_lossy().to_string(),
+        File { items: vec![item] },
     )]));
-    // get currently-compiling crate name
-    let crate_name = tcx.crate_name(LOCAL_CRATE).to_string();
-    let ws = Workspace(HashMap::from([(crate_name.clone(), krate)]));
+    let ws = Workspace(HashMap::from([(crate_name.to_owned(), krate)]));
     println!("{}", serde_json::to_string(&ws).unwrap());
+    RESULTS.lock().unwrap().merge(ws);
 }
 
 #[rustversion::since(1.95.0)]
@@ -161,10 +310,11 @@ pub fn run_compiler() -> ExitCode {
     // by using `RUSTC_WORKSPACE_WRAPPER`, arguments will be as follows:
     // For dependencies: rustowlc [args...]
     // For user workspace: rustowlc rustowlc [args...]
-    // So we skip analysis if currently-compiling crate is one of the dependencies
+    // So we skip analysis if currently-compiling crate is one of the dependencies,
+    // unless the dependency is analyzed on demand
     if args.first() == args.get(1) {
         args = args.into_iter().skip(1).collect();
-    } else {
+    } else if !rustowl::cache::is_selected_dependency() {
         return handle_exit_code(rustc_driver::catch_with_exit_code(|| {
             rustc_driver::run_compiler(&args, &mut RustcCallback)
         }));
@@ -179,6 +329,7 @@ pub fn run_compiler() -> ExitCode {
         }
     }
 
+    budget::start_watchdog(args.iter().any(|v| v == "--error-format=json"));
     handle_exit_code(rustc_driver::catch_with_exit_code(|| {
         rustc_driver::run_compiler(&args, &mut AnalyzerCallback);
     }))
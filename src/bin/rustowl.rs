@@ -26,6 +26,22 @@ fn set_log_level(default: log::LevelFilter) {
     );
 }
 
+const TOOLCHAIN_ERROR_EXIT_CODE: i32 = 4;
+
+/// Maps analyzer errors to the exit codes documented in [`handle_command`].
+fn analyzer_exit_code(error: &rustowl::lsp::analyze::AnalyzerError) -> i32 {
+    use rustowl::lsp::analyze::AnalyzerError;
+    match error {
+        AnalyzerError::NoCargoToml(_) => 2,
+        AnalyzerError::CargoMetadata { .. }
+        | AnalyzerError::RustProject { .. }
+        | AnalyzerError::CargoConfig(_)
+        | AnalyzerError::Config(_) => 3,
+        AnalyzerError::Toolchain(_) => TOOLCHAIN_ERROR_EXIT_CODE,
+        AnalyzerError::Command { .. } => 1,
+    }
+}
+
 /// Handles the execution of RustOwl CLI commands.
 ///
 /// This function processes a specific CLI command and executes the appropriate
@@ -40,24 +56,36 @@ fn set_log_level(default: log::LevelFilter) {
 ///
 /// This function may exit the process with appropriate exit codes:
 /// - Exit code 0 on successful analysis
-/// - Exit code 1 on analysis failure or toolchain setup errors
+/// - Exit code 1 on analysis failure
+/// - Exit code 2 when no Cargo.toml is found
+/// - Exit code 3 when `cargo metadata` fails, or `rust-project.json`, `.cargo/config.toml` or the configuration is invalid
+/// - Exit code 4 on toolchain setup errors
 async fn handle_command(command: Commands) {
     match command {
         Commands::Check(command_options) => {
             let path = command_options.path.unwrap_or(env::current_dir().unwrap());
 
-            if Backend::check_with_options(
-                &path,
-                command_options.all_targets,
-                command_options.all_features,
-            )
-            .await
-            {
-                log::info!("Successfully analyzed");
-                std::process::exit(0);
+            let options = rustowl::lsp::analyze::AnalyzeOptions {
+                all_targets: command_options.all_targets,
+                all_features: command_options.all_features,
+                budget: command_options.budget.into(),
+                targets: command_options.target,
+                ..Default::default()
+            };
+            match Backend::check_with_options(&path, options, command_options.cargo.into()).await {
+                Ok(true) => {
+                    log::info!("Successfully analyzed");
+                    std::process::exit(0);
+                }
+                Ok(false) => {
+                    log::error!("Analyze failed");
+                    std::process::exit(1);
+                }
+                Err(e) => {
+                    log::error!("{e}");
+                    std::process::exit(analyzer_exit_code(&e));
+                }
             }
-            log::error!("Analyze failed");
-            std::process::exit(1);
         }
         Commands::Clean => {
             if let Ok(meta) = cargo_metadata::MetadataCommand::new().exec() {
@@ -73,15 +101,18 @@ async fn handle_command(command: Commands) {
                         skip_rustowl_toolchain,
                     } => {
                         let path = path.unwrap_or(toolchain::FALLBACK_RUNTIME_DIR.clone());
-                        if toolchain::setup_toolchain(&path, skip_rustowl_toolchain)
-                            .await
-                            .is_err()
+                        if let Err(e) =
+                            toolchain::setup_toolchain(&path, skip_rustowl_toolchain).await
                         {
-                            std::process::exit(1);
+                            log::error!("{e}");
+                            std::process::exit(TOOLCHAIN_ERROR_EXIT_CODE);
                         }
                     }
                     ToolchainCommands::Uninstall => {
-                        rustowl::toolchain::uninstall_toolchain().await;
+                        if let Err(e) = rustowl::toolchain::uninstall_toolchain().await {
+                            log::error!("{e}");
+                            std::process::exit(TOOLCHAIN_ERROR_EXIT_CODE);
+                        }
                     }
                 }
             }
@@ -112,22 +143,45 @@ async fn handle_show_command(opts: cli::Show) {
     log::info!("Analyzing project at {path:?}");
 
     // Create an analyzer and run analysis
-    let analyzer = match Analyzer::new(&path).await {
+    let analyzer = match Analyzer::new(&path, opts.cargo.into()).await {
         Ok(a) => a,
         Err(e) => {
-            log::error!("Failed to create analyzer: {e:?}");
-            std::process::exit(1);
+            log::error!("Failed to create analyzer: {e}");
+            std::process::exit(analyzer_exit_code(&e));
         }
     };
 
-    let mut iter = analyzer.analyze(opts.all_targets, opts.all_features).await;
+    // only the package target which the file belongs to is needed
+    let options = rustowl::lsp::analyze::AnalyzeOptions {
+        all_targets: opts.all_targets,
+        all_features: opts.all_features,
+        budget: opts.budget.into(),
+        targets: opts.target.iter().cloned().collect(),
+        ..Default::default()
+    };
+    let iter = if let Some(target) = file_path
+        .as_ref()
+        .and_then(|v| analyzer.package_target(v))
+        .filter(|_| !opts.all_targets)
+    {
+        analyzer.analyze_target(&target, &options).await
+    } else {
+        analyzer.analyze(&options).await
+    };
+    let mut iter = match iter {
+        Ok(v) => v,
+        Err(e) => {
+            log::error!("{e}");
+            std::process::exit(analyzer_exit_code(&e));
+        }
+    };
 
     // Collect analysis results
     let mut crate_data: Option<rustowl::models::Crate> = None;
     while let Some(event) = iter.next_event().await {
         match event {
-            rustowl::lsp::analyze::AnalyzerEvent::Analyzed(ws) => {
-                for krate in ws.0.into_values() {
+            rustowl::lsp::analyze::AnalyzerEvent::Analyzed { workspace, .. } => {
+                for krate in workspace.0.into_values() {
                     if let Some(existing) = &mut crate_data {
                         existing.merge(krate);
                     } else {
@@ -135,8 +189,23 @@ async fn handle_show_command(opts: cli::Show) {
                     }
                 }
             }
-            rustowl::lsp::analyze::AnalyzerEvent::CrateChecked { package, .. } => {
-                log::debug!("Analyzed: {package}");
+            rustowl::lsp::analyze::AnalyzerEvent::CrateChecked {
+                package,
+                workspace_member,
+                ..
+            } => {
+                if workspace_member {
+                    log::debug!("Analyzed: {package}");
+                } else {
+                    log::debug!("Compiled dependency: {package}");
+                }
+            }
+            rustowl::lsp::analyze::AnalyzerEvent::Diagnostic { message, .. } => {
+                if message.is_error()
+                    && let Some(rendered) = &message.rendered
+                {
+                    eprint!("{rendered}");
+                }
             }
         }
     }
@@ -155,6 +224,8 @@ async fn handle_show_command(opts: cli::Show) {
         file_path.as_deref(),
         &opts.function_path,
         &opts.variable,
+        &analyzer.config().decoration,
+        opts.explain,
     ) {
         log::error!("{e}");
         std::process::exit(1);
@@ -200,6 +271,8 @@ async fn start_lsp_server() {
     let (service, socket) = LspService::build(Backend::new)
         .custom_method("rustowl/cursor", Backend::cursor)
         .custom_method("rustowl/analyze", Backend::analyze)
+        .custom_method("rustowl/selectTarget", Backend::select_target)
+        .custom_method("rustowl/explainBorrow", Backend::explain_borrow)
         .finish();
 
     Server::new(stdin, stdout, socket).serve(service).await;
@@ -0,0 +1,91 @@
+//! Time and memory budgets of analysis
+//!
+//! Budgets are passed from the analyzer to rustowlc by environment variables.
+
+use std::env;
+use std::time::Duration;
+use tokio::process::Command;
+
+const FUNCTION_TIMEOUT_ENV: &str = "RUSTOWL_FUNCTION_TIMEOUT";
+const FUNCTION_MEMORY_LIMIT_ENV: &str = "RUSTOWL_FUNCTION_MEMORY_LIMIT";
+const CRATE_TIMEOUT_ENV: &str = "RUSTOWL_CRATE_TIMEOUT";
+const CRATE_MEMORY_LIMIT_ENV: &str = "RUSTOWL_CRATE_MEMORY_LIMIT";
+/// Environment variables which affect the analysis results,
+/// so that cargo re-runs rustowlc when they are changed
+pub const ENV_VARS: [&str; 4] = [
+    FUNCTION_TIMEOUT_ENV,
+    FUNCTION_MEMORY_LIMIT_ENV,
+    CRATE_TIMEOUT_ENV,
+    CRATE_MEMORY_LIMIT_ENV,
+];
+
+/// Diagnostic code of the error rustowlc reports when it aborts a crate
+pub const BUDGET_EXCEEDED_CODE: &str = "rustowl::budget_exceeded";
+
+/// Limits of analysis; no limit is applied by default
+#[derive(serde::Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case", default)]
+pub struct Budget {
+    /// Seconds to analyze a function before the function is skipped as timed out
+    pub function_timeout: Option<u64>,
+    /// MiB of memory the analysis of a function may allocate before the function is skipped
+    pub function_memory_limit: Option<u64>,
+    /// Seconds to analyze a crate before the compiler process is aborted
+    pub crate_timeout: Option<u64>,
+    /// MiB of memory the compiler process may use before it is aborted
+    pub crate_memory_limit: Option<u64>,
+}
+
+impl From<crate::cli::Budget> for Budget {
+    fn from(value: crate::cli::Budget) -> Self {
+        Self {
+            function_timeout: value.function_timeout,
+            function_memory_limit: value.function_memory_limit,
+            crate_timeout: value.crate_timeout,
+            crate_memory_limit: value.crate_memory_limit,
+        }
+    }
+}
+
+impl Budget {
+    pub fn set_env(&self, cmd: &mut Command) {
+        for (key, value) in [
+            (FUNCTION_TIMEOUT_ENV, self.function_timeout),
+            (FUNCTION_MEMORY_LIMIT_ENV, self.function_memory_limit),
+            (CRATE_TIMEOUT_ENV, self.crate_timeout),
+            (CRATE_MEMORY_LIMIT_ENV, self.crate_memory_limit),
+        ] {
+            if let Some(value) = value {
+                cmd.env(key, value.to_string());
+            }
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let get = |key| env::var(key).ok().and_then(|v| v.parse().ok());
+        Self {
+            function_timeout: get(FUNCTION_TIMEOUT_ENV),
+            function_memory_limit: get(FUNCTION_MEMORY_LIMIT_ENV),
+            crate_timeout: get(CRATE_TIMEOUT_ENV),
+            crate_memory_limit: get(CRATE_MEMORY_LIMIT_ENV),
+        }
+    }
+
+    pub fn function_timeout(&self) -> Option<Duration> {
+        self.function_timeout.map(Duration::from_secs)
+    }
+
+    /// Memory limit of a function in bytes
+    pub fn function_memory_limit_bytes(&self) -> Option<u64> {
+        self.function_memory_limit.map(|v| v * 1024 * 1024)
+    }
+
+    pub fn crate_timeout(&self) -> Option<Duration> {
+        self.crate_timeout.map(Duration::from_secs)
+    }
+
+    /// Memory limit of a crate in bytes
+    pub fn crate_memory_limit_bytes(&self) -> Option<u64> {
+        self.crate_memory_limit.map(|v| v * 1024 * 1024)
+    }
+}
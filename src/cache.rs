@@ -8,10 +8,73 @@ pub fn is_cache() -> bool {
         .unwrap_or(false)
 }
 
-pub fn set_cache_path(cmd: &mut Command, target_dir: impl AsRef<Path>) {
-    cmd.env("RUSTOWL_CACHE_DIR", target_dir.as_ref().join("cache"));
+/// Default directory of the incremental cache
+pub fn cache_dir(target_dir: impl AsRef<Path>) -> PathBuf {
+    target_dir.as_ref().join("cache")
+}
+
+pub fn set_cache_path(cmd: &mut Command, cache_dir: impl AsRef<Path>) {
+    cmd.env("RUSTOWL_CACHE_DIR", cache_dir.as_ref());
 }
 
 pub fn get_cache_path() -> Option<PathBuf> {
     env::var("RUSTOWL_CACHE_DIR").map(PathBuf::from).ok()
 }
+
+/// Version of the format of stored analysis results, which is bumped when the models change
+/// within a version of RustOwl, so that results stored before are not read with missing fields
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Name of the directory where results of this format are stored
+fn format_dir() -> String {
+    format!("{}-{FORMAT_VERSION}", env!("CARGO_PKG_VERSION"))
+}
+
+/// Incremental cache file of a crate in the cache directory
+///
+/// Caches are kept for each format of the results, as [`result_dir`] does.
+pub fn cache_file(cache_dir: impl AsRef<Path>, krate: &str) -> PathBuf {
+    cache_dir
+        .as_ref()
+        .join(format_dir())
+        .join(format!("{krate}.json"))
+}
+
+/// Dependency package analyzed on demand, specified by its manifest directory.
+/// rustowlc analyzes the package although it is not a workspace member.
+pub fn set_dependency_path(cmd: &mut Command, manifest_dir: impl AsRef<Path>) {
+    cmd.env("RUSTOWL_DEPENDENCY_DIR", manifest_dir.as_ref());
+}
+
+/// Whether the currently-compiling package is the dependency analyzed on demand
+pub fn is_selected_dependency() -> bool {
+    match (
+        env::var_os("RUSTOWL_DEPENDENCY_DIR"),
+        env::var_os("CARGO_MANIFEST_DIR"),
+    ) {
+        (Some(selected), Some(current)) => Path::new(&selected) == Path::new(&current),
+        _ => false,
+    }
+}
+
+/// Directory where rustowlc stores analysis results of each compilation unit.
+/// Results are replayed when cargo does not re-run rustowlc for a unit.
+///
+/// Results are kept for each version of RustOwl and [`FORMAT_VERSION`], since the format may change.
+pub fn result_dir(target_dir: impl AsRef<Path>) -> PathBuf {
+    target_dir.as_ref().join("results").join(format_dir())
+}
+
+pub fn set_result_path(cmd: &mut Command, target_dir: impl AsRef<Path>) {
+    cmd.env("RUSTOWL_RESULT_DIR", result_dir(target_dir));
+}
+
+pub fn get_result_path() -> Option<PathBuf> {
+    env::var("RUSTOWL_RESULT_DIR").map(PathBuf::from).ok()
+}
+
+/// Path of the stored result corresponding to the metadata file (`.rmeta`) of a unit
+pub fn result_file(result_dir: impl AsRef<Path>, rmeta: impl AsRef<Path>) -> Option<PathBuf> {
+    let stem = rmeta.as_ref().file_stem()?;
+    Some(result_dir.as_ref().join(stem).with_extension("json"))
+}
@@ -62,6 +62,51 @@ pub struct Check {
         help = "Run the check for all features instead of the current active ones only"
     )]
     pub all_features: bool,
+
+    /// Target triples to check for, instead of the default target
+    /// (can be specified multiple times).
+    #[arg(long, value_name("triple"))]
+    pub target: Vec<String>,
+    #[command(flatten)]
+    pub budget: Budget,
+    #[command(flatten)]
+    pub cargo: CargoMode,
+}
+
+/// Modes passed to cargo
+#[derive(Args, Clone, Copy, Debug)]
+pub struct CargoMode {
+    /// Run cargo without accessing the network.
+    #[arg(long)]
+    pub offline: bool,
+
+    /// Require Cargo.lock to be up to date.
+    #[arg(long)]
+    pub locked: bool,
+
+    /// Require Cargo.lock to be up to date and run cargo without accessing the network.
+    #[arg(long)]
+    pub frozen: bool,
+}
+
+/// Limits of analysis; no limit is applied by default
+#[derive(Args, Clone, Copy, Debug)]
+pub struct Budget {
+    /// Seconds to analyze a function before the function is skipped as timed out.
+    #[arg(long, value_name("secs"))]
+    pub function_timeout: Option<u64>,
+
+    /// MiB of memory the analysis of a function may allocate before the function is skipped.
+    #[arg(long, value_name("mib"))]
+    pub function_memory_limit: Option<u64>,
+
+    /// Seconds to analyze a crate before the compiler process is aborted.
+    #[arg(long, value_name("secs"))]
+    pub crate_timeout: Option<u64>,
+
+    /// MiB of memory the compiler process may use before it is aborted.
+    #[arg(long, value_name("mib"))]
+    pub crate_memory_limit: Option<u64>,
 }
 
 #[derive(Args, Debug)]
@@ -112,7 +157,7 @@ pub struct Show {
     #[arg(value_name("function_path"))]
     pub function_path: String,
 
-    /// The name of the variable to visualize.
+    /// The name of the variable to visualize, or a lifetime parameter such as `'a`.
     #[arg(value_name("variable"))]
     pub variable: String,
 
@@ -123,4 +168,16 @@ pub struct Show {
     /// Check all features.
     #[arg(long, default_value_t = false)]
     pub all_features: bool,
+
+    /// Target triple to analyze for, instead of the default target.
+    #[arg(long, value_name("triple"))]
+    pub target: Option<String>,
+
+    /// Explain why each borrow of the variable lives as long as it does.
+    #[arg(long, default_value_t = false)]
+    pub explain: bool,
+    #[command(flatten)]
+    pub budget: Budget,
+    #[command(flatten)]
+    pub cargo: CargoMode,
 }
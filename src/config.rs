@@ -0,0 +1,260 @@
+//! Project configuration, read from `rustowl.toml` or
+//! `[workspace.metadata.rustowl]` of `Cargo.toml`
+
+use serde::Deserialize;
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+pub const CONFIG_FILE_NAMES: [&str; 2] = ["rustowl.toml", ".rustowl.toml"];
+
+const EXCLUDE_PATHS_ENV: &str = "RUSTOWL_EXCLUDE_PATHS";
+const EXCLUDE_FUNCTIONS_ENV: &str = "RUSTOWL_EXCLUDE_FUNCTIONS";
+
+/// Environment variables which affect the analysis results,
+/// so that cargo re-runs rustowlc when they are changed
+pub const ENV_VARS: [&str; 2] = [EXCLUDE_PATHS_ENV, EXCLUDE_FUNCTIONS_ENV];
+
+#[derive(Deserialize, Clone, Default, Debug)]
+#[serde(rename_all = "snake_case", default)]
+pub struct Config {
+    /// Features passed to `cargo check --features`
+    pub features: Vec<String>,
+    pub all_features: bool,
+    /// Target triples to analyze for, used when no target is specified by the client
+    pub targets: Vec<String>,
+    /// Workspace packages (or crates of `rust-project.json`) not analyzed
+    pub exclude_packages: Vec<String>,
+    /// Files and directories not analyzed, relative to the configuration
+    pub exclude_paths: Vec<PathBuf>,
+    /// Functions or modules not analyzed, e.g. `generated` or `parser::parse`
+    pub exclude_functions: Vec<String>,
+    /// Directory of the incremental cache, relative to the configuration
+    pub cache_dir: Option<PathBuf>,
+    pub decoration: DecorationConfig,
+    #[serde(flatten)]
+    pub cargo: CargoMode,
+}
+
+/// Modes passed to every cargo invocation, so that cargo does not access the network
+/// or update `Cargo.lock`
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case", default)]
+pub struct CargoMode {
+    pub offline: bool,
+    pub locked: bool,
+    pub frozen: bool,
+}
+
+impl From<crate::cli::CargoMode> for CargoMode {
+    fn from(value: crate::cli::CargoMode) -> Self {
+        Self {
+            offline: value.offline,
+            locked: value.locked,
+            frozen: value.frozen,
+        }
+    }
+}
+
+impl CargoMode {
+    /// Modes set by cargo's environment variables, e.g. `CARGO_NET_OFFLINE=true`
+    pub fn from_env() -> Self {
+        Self {
+            offline: env::var("CARGO_NET_OFFLINE").is_ok_and(|v| v == "true" || v == "1"),
+            ..Default::default()
+        }
+    }
+
+    /// Modes enabled in either of them
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            offline: self.offline || other.offline,
+            locked: self.locked || other.locked,
+            frozen: self.frozen || other.frozen,
+        }
+    }
+
+    pub fn args(&self) -> Vec<&'static str> {
+        [
+            (self.offline, "--offline"),
+            (self.locked, "--locked"),
+            (self.frozen, "--frozen"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, arg)| enabled.then_some(arg))
+        .collect()
+    }
+}
+
+#[derive(Deserialize, Clone, Default, Debug)]
+#[serde(rename_all = "snake_case", default)]
+pub struct DecorationConfig {
+    /// Types of decorations not shown, e.g. `call` or `lifetime`
+    pub hidden: Vec<String>,
+}
+impl DecorationConfig {
+    pub fn is_hidden(&self, kind: &str) -> bool {
+        self.hidden.iter().any(|v| v == kind)
+    }
+}
+
+/// Invalid configuration
+#[derive(Debug)]
+pub struct ConfigError {
+    pub path: PathBuf,
+    pub message: String,
+}
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid configuration in {}: {}",
+            self.path.display(),
+            self.message
+        )
+    }
+}
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Find the configuration file in `dir` or its ancestors, up to `root` if specified
+    pub fn find(dir: &Path, root: Option<&Path>) -> Option<PathBuf> {
+        for ancestor in dir.ancestors() {
+            for name in CONFIG_FILE_NAMES {
+                let path = ancestor.join(name);
+                if path.is_file() {
+                    return Some(path);
+                }
+            }
+            if Some(ancestor) == root {
+                break;
+            }
+        }
+        None
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let error = |message| ConfigError {
+            path: path.to_path_buf(),
+            message,
+        };
+        let source = std::fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+        Self::parse(&source, path.parent().unwrap()).map_err(error)
+    }
+
+    /// Read the configuration from the content of `rustowl.toml`
+    ///
+    /// Relative paths are resolved against `root`.
+    fn parse(source: &str, root: &Path) -> Result<Self, String> {
+        let config: Self = toml::from_str(source).map_err(|e| e.to_string())?;
+        Ok(config.resolve_paths(root))
+    }
+
+    /// Read the configuration from a parsed value, such as `[workspace.metadata.rustowl]`
+    ///
+    /// Relative paths are resolved against `root`.
+    pub fn from_value(value: serde_json::Value, root: &Path) -> Result<Self, String> {
+        let config: Self = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        Ok(config.resolve_paths(root))
+    }
+
+    fn resolve_paths(mut self, root: &Path) -> Self {
+        for path in &mut self.exclude_paths {
+            *path = root.join(&*path);
+        }
+        if let Some(cache_dir) = &mut self.cache_dir {
+            *cache_dir = root.join(&*cache_dir);
+        }
+        self
+    }
+
+    pub fn is_package_excluded(&self, package: &str) -> bool {
+        self.exclude_packages.iter().any(|v| v == package)
+    }
+
+    /// Pass the excluded paths and functions to rustowlc
+    pub fn set_env(&self, cmd: &mut Command) {
+        if let Ok(paths) = env::join_paths(&self.exclude_paths) {
+            cmd.env(EXCLUDE_PATHS_ENV, paths);
+        }
+        cmd.env(EXCLUDE_FUNCTIONS_ENV, self.exclude_functions.join("\n"));
+    }
+}
+
+/// Paths and functions excluded from the analysis, read by rustowlc
+#[derive(Clone, Default, Debug)]
+pub struct Exclusion {
+    paths: Vec<PathBuf>,
+    functions: Vec<String>,
+}
+impl Exclusion {
+    pub fn from_env() -> Self {
+        let paths = env::var_os(EXCLUDE_PATHS_ENV)
+            .map(|v| {
+                env::split_paths(&v)
+                    .filter(|v| !v.as_os_str().is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let functions = env::var(EXCLUDE_FUNCTIONS_ENV)
+            .map(|v| {
+                v.lines()
+                    .filter(|v| !v.is_empty())
+                    .map(str::to_owned)
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self { paths, functions }
+    }
+
+    /// Whether the function is excluded by its file or by its path or the path of its module
+    pub fn is_excluded(&self, file: &Path, function: &str) -> bool {
+        self.paths.iter().any(|v| file.starts_with(v))
+            || self.functions.iter().any(|v| {
+                function == v
+                    || function
+                        .strip_prefix(v.as_str())
+                        .is_some_and(|rest| rest.starts_with("::"))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exclude_function_and_module() {
+        let exclusion = Exclusion {
+            paths: vec![PathBuf::from("/ws/src/generated")],
+            functions: vec!["parser".to_owned(), "lexer::next".to_owned()],
+        };
+        let file = Path::new("/ws/src/lib.rs");
+        assert!(exclusion.is_excluded(file, "parser::parse"));
+        assert!(exclusion.is_excluded(file, "lexer::next::{closure#0}"));
+        assert!(!exclusion.is_excluded(file, "parser_utils::parse"));
+        assert!(!exclusion.is_excluded(file, "lexer::next_token"));
+        assert!(exclusion.is_excluded(Path::new("/ws/src/generated/a.rs"), "a::f"));
+    }
+
+    #[test]
+    fn cargo_mode_from_config() {
+        let config = Config::parse(
+            "offline = true\nexclude_paths = [\n  \"gen\", # generated\n  'vendor',\n]\n\n[decoration]\nhidden = [\"call\"]\n",
+            Path::new("/ws"),
+        )
+        .unwrap();
+        assert_eq!(
+            config.exclude_paths,
+            vec![PathBuf::from("/ws/gen"), PathBuf::from("/ws/vendor")]
+        );
+        assert!(config.decoration.is_hidden("call"));
+        assert!(Config::parse("features = \"a\"\n", Path::new("/ws")).is_err());
+        let cargo = config.cargo.union(CargoMode {
+            locked: true,
+            ..Default::default()
+        });
+        assert_eq!(cargo.args(), vec!["--offline", "--locked"]);
+    }
+}
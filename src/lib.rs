@@ -2,8 +2,10 @@
 //!
 //! Libraries that used in RustOwl
 
+pub mod budget;
 pub mod cache;
 pub mod cli;
+pub mod config;
 pub mod lsp;
 pub mod models;
 pub mod shells;
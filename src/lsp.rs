@@ -1,4 +1,13 @@
 pub mod analyze;
+pub mod await_points;
 pub mod backend;
+pub mod borrow_graph;
+pub mod cargo_config;
 pub mod decoration;
+pub mod diagnostic;
+pub mod explain;
 pub mod progress;
+pub mod rust_project;
+pub mod scheduler;
+pub mod single_file;
+pub mod symbol;
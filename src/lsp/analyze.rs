@@ -1,4 +1,7 @@
-use crate::{cache::*, models::*, toolchain};
+use super::{cargo_config, rust_project::RustProject, single_file};
+use crate::{budget::Budget, cache::*, config::*, models::*, toolchain};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::sync::Arc;
@@ -12,138 +15,860 @@ use tokio::{
 pub struct CargoCheckMessageTarget {
     name: String,
 }
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct CompilerMessageSpan {
+    pub file_name: PathBuf,
+    pub line_start: u32,
+    pub line_end: u32,
+    pub column_start: u32,
+    pub column_end: u32,
+    pub is_primary: bool,
+}
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct CompilerMessageCode {
+    pub code: String,
+}
+/// Diagnostic emitted by the compiler in JSON format
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct CompilerMessage {
+    pub message: String,
+    pub level: String,
+    pub code: Option<CompilerMessageCode>,
+    pub spans: Vec<CompilerMessageSpan>,
+    pub rendered: Option<String>,
+}
+impl CompilerMessage {
+    pub fn is_error(&self) -> bool {
+        self.level == "error"
+    }
+}
+
 #[derive(serde::Deserialize, Clone, Debug)]
 #[serde(tag = "reason", rename_all = "kebab-case")]
 pub enum CargoCheckMessage {
     #[allow(unused)]
-    CompilerArtifact { target: CargoCheckMessageTarget },
+    CompilerArtifact {
+        package_id: String,
+        target: CargoCheckMessageTarget,
+        fresh: bool,
+        filenames: Vec<PathBuf>,
+    },
+    CompilerMessage {
+        target: CargoCheckMessageTarget,
+        message: CompilerMessage,
+    },
     #[allow(unused)]
     BuildFinished {},
 }
 
 pub enum AnalyzerEvent {
+    /// A unit of the build plan is compiled
     CrateChecked {
         package: String,
-        package_count: usize,
+        /// Whether the unit belongs to a workspace member analyzed by rustowlc,
+        /// not a dependency
+        workspace_member: bool,
+        /// Estimated number of units `cargo check` compiles
+        unit_count: usize,
+    },
+    /// Results of functions analyzed for the target triple
+    Analyzed {
+        triple: String,
+        workspace: Workspace,
     },
-    Analyzed(Workspace),
+    /// Compiler diagnostic, whose file names are resolved to absolute paths
+    Diagnostic {
+        package: String,
+        message: CompilerMessage,
+    },
+}
+
+/// Target of a package to be analyzed, selected by `cargo check` options
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TargetSelection {
+    Lib,
+    Bin(String),
+    Test(String),
+    Example(String),
+    Bench(String),
+}
+
+/// A package target which a source file belongs to
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PackageTarget {
+    pub package: String,
+    pub target: TargetSelection,
+}
+impl PackageTarget {
+    fn from_target(package: &cargo_metadata::Package, target: &cargo_metadata::Target) -> Self {
+        let name = target.name.clone();
+        let target = if target.is_bin() {
+            TargetSelection::Bin(name)
+        } else if target.is_test() {
+            TargetSelection::Test(name)
+        } else if target.is_example() {
+            TargetSelection::Example(name)
+        } else if target.is_bench() {
+            TargetSelection::Bench(name)
+        } else {
+            TargetSelection::Lib
+        };
+        Self {
+            package: package.name.to_string(),
+            target,
+        }
+    }
+
+    fn cargo_args(&self) -> Vec<String> {
+        let mut args = vec!["--package".to_owned(), self.package.clone()];
+        match &self.target {
+            TargetSelection::Lib => args.push("--lib".to_owned()),
+            TargetSelection::Bin(name) => args.extend(["--bin".to_owned(), name.clone()]),
+            TargetSelection::Test(name) => args.extend(["--test".to_owned(), name.clone()]),
+            TargetSelection::Example(name) => args.extend(["--example".to_owned(), name.clone()]),
+            TargetSelection::Bench(name) => args.extend(["--bench".to_owned(), name.clone()]),
+        }
+        args
+    }
+}
+
+/// A dependency package which a source file belongs to, such as a path dependency
+/// or a crate in the registry
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DependencyPackage {
+    pub name: String,
+    pub version: String,
+    pub manifest_dir: PathBuf,
+}
+impl DependencyPackage {
+    fn package_target(&self) -> PackageTarget {
+        PackageTarget {
+            package: format!("{}@{}", self.name, self.version),
+            target: TargetSelection::Lib,
+        }
+    }
+}
+
+/// Options of analysis, which correspond to `cargo check` options
+#[derive(Clone, Default, Debug)]
+pub struct AnalyzeOptions {
+    pub all_targets: bool,
+    pub all_features: bool,
+    /// Number of compiler processes run in parallel
+    pub jobs: Option<usize>,
+    pub budget: Budget,
+    /// Target triples to analyze for, instead of the default target
+    pub targets: Vec<String>,
+}
+
+/// Errors that occur while preparing analysis
+#[derive(Debug)]
+pub enum AnalyzerError {
+    /// Neither a `Cargo.toml` nor a Rust source file was found at the path
+    NoCargoToml(PathBuf),
+    /// `cargo metadata` failed for the package at the path
+    CargoMetadata {
+        path: PathBuf,
+        message: String,
+    },
+    /// `rust-project.json` at the path could not be loaded
+    RustProject {
+        path: PathBuf,
+        message: String,
+    },
+    /// A Cargo configuration file (`.cargo/config.toml`) could not be parsed
+    CargoConfig(cargo_config::CargoConfigError),
+    /// Failed to run an external command
+    Command {
+        program: String,
+        source: std::io::Error,
+    },
+    Toolchain(toolchain::ToolchainError),
+    Config(ConfigError),
+}
+
+impl AnalyzerError {
+    fn command(program: impl Into<String>) -> impl FnOnce(std::io::Error) -> Self {
+        let program = program.into();
+        move |source| Self::Command { program, source }
+    }
+}
+
+impl fmt::Display for AnalyzerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalyzerError::NoCargoToml(path) => {
+                write!(f, "could not find Cargo.toml for {}", path.display())
+            }
+            AnalyzerError::CargoMetadata { path, message } => {
+                write!(f, "cargo metadata failed in {}: {message}", path.display())
+            }
+            AnalyzerError::RustProject { path, message } => {
+                write!(f, "failed to load {}: {message}", path.display())
+            }
+            AnalyzerError::CargoConfig(e) => {
+                write!(f, "failed to load {}: {}", e.path.display(), e.message)
+            }
+            AnalyzerError::Command { program, source } => {
+                write!(f, "failed to run {program}: {source}")
+            }
+            AnalyzerError::Toolchain(e) => write!(f, "toolchain setup failed: {e}"),
+            AnalyzerError::Config(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for AnalyzerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AnalyzerError::Command { source, .. } => Some(source),
+            AnalyzerError::Toolchain(e) => Some(e),
+            AnalyzerError::Config(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<toolchain::ToolchainError> for AnalyzerError {
+    fn from(e: toolchain::ToolchainError) -> Self {
+        AnalyzerError::Toolchain(e)
+    }
+}
+
+impl From<ConfigError> for AnalyzerError {
+    fn from(e: ConfigError) -> Self {
+        AnalyzerError::Config(e)
+    }
+}
+
+/// Load the configuration file found between `dir` and `root`
+fn load_config(dir: &Path, root: Option<&Path>) -> Result<Option<Config>, ConfigError> {
+    Config::find(dir, root)
+        .map(|path| Config::load(&path))
+        .transpose()
 }
 
 #[derive(Clone)]
 pub struct Analyzer {
     path: PathBuf,
     metadata: Option<cargo_metadata::Metadata>,
+    rust_project: Option<RustProject>,
+    /// Cargo script, which is a single-file package with an embedded manifest
+    script: Option<PathBuf>,
+    /// `build.target` of the Cargo configuration
+    build_targets: Vec<String>,
+    config: Config,
+    /// Modes passed to cargo, of the client, the environment and the configuration
+    cargo: CargoMode,
 }
 
 impl Analyzer {
-    pub async fn new(path: impl AsRef<Path>) -> Result<Self, ()> {
+    pub async fn new(path: impl AsRef<Path>, cargo: CargoMode) -> Result<Self, AnalyzerError> {
         let path = path.as_ref().to_path_buf();
+        let cargo = cargo.union(CargoMode::from_env());
+        let dir = if path.is_file() {
+            path.parent().unwrap()
+        } else {
+            &path
+        };
 
-        let mut cargo_cmd = toolchain::setup_cargo_command().await;
+        let script = Some(path.clone()).filter(|v| {
+            v.is_file()
+                && std::fs::read_to_string(v)
+                    .map(|source| single_file::has_cargo_frontmatter(&source))
+                    .unwrap_or(false)
+        });
+
+        if script.is_none()
+            && let Some(project_path) = RustProject::find(dir)
+        {
+            let rust_project =
+                RustProject::load(&project_path).map_err(|message| AnalyzerError::RustProject {
+                    path: project_path,
+                    message,
+                })?;
+            let config = load_config(dir, Some(rust_project.root()))?.unwrap_or_default();
+            return Ok(Self {
+                path: rust_project.root().to_path_buf(),
+                metadata: None,
+                rust_project: Some(rust_project),
+                script: None,
+                build_targets: Vec::new(),
+                cargo: cargo.union(config.cargo),
+                config,
+            });
+        }
+
+        // dependencies are resolved for the targets which `cargo check` builds for by default
+        let build_targets = cargo_config::build_targets(dir).map_err(AnalyzerError::CargoConfig)?;
+        let mut platforms = build_targets.clone();
+        if platforms.is_empty() {
+            platforms.push(toolchain::HOST_TUPLE.to_owned());
+        }
+
+        // the workspace root is not known yet, but `cargo metadata` needs the modes
+        // of the configuration file; files outside of the workspace are ignored afterwards
+        let config_file = Config::find(dir, None).map(|path| {
+            let config = Config::load(&path);
+            (path, config)
+        });
+        let metadata_cargo = config_file
+            .as_ref()
+            .and_then(|(_, config)| config.as_ref().ok())
+            .map(|config| cargo.union(config.cargo))
+            .unwrap_or(cargo);
+
+        let mut cargo_cmd = toolchain::setup_cargo_command().await?;
 
         cargo_cmd
-            .args([
-                "metadata".to_owned(),
-                "--filter-platform".to_owned(),
-                toolchain::HOST_TUPLE.to_owned(),
-            ])
-            .current_dir(if path.is_file() {
-                path.parent().unwrap()
-            } else {
-                &path
-            })
+            .arg("metadata")
+            .args(metadata_cargo.args())
+            .args(
+                platforms
+                    .iter()
+                    .flat_map(|v| ["--filter-platform".to_owned(), v.clone()]),
+            )
+            .args(script_args(script.as_deref()))
+            .current_dir(dir)
             .stdout(Stdio::piped())
-            .stderr(Stdio::null());
+            .stderr(Stdio::piped());
 
-        let metadata = if let Ok(child) = cargo_cmd.spawn()
-            && let Ok(output) = child.wait_with_output().await
-        {
+        let output = cargo_cmd
+            .spawn()
+            .map_err(AnalyzerError::command("cargo metadata"))?
+            .wait_with_output()
+            .await
+            .map_err(AnalyzerError::command("cargo metadata"))?;
+        let metadata = if output.status.success() {
             let data = String::from_utf8_lossy(&output.stdout);
-            cargo_metadata::MetadataCommand::parse(data).ok()
+            cargo_metadata::MetadataCommand::parse(data).map_err(|e| e.to_string())
         } else {
-            None
+            Err(String::from_utf8_lossy(&output.stderr).trim().to_owned())
         };
 
-        if let Some(metadata) = metadata {
-            Ok(Self {
-                path: metadata.workspace_root.as_std_path().to_path_buf(),
-                metadata: Some(metadata),
-            })
-        } else if path.is_file() && path.extension().map(|v| v == "rs").unwrap_or(false) {
-            Ok(Self {
-                path,
-                metadata: None,
-            })
-        } else {
-            log::warn!("Invalid analysis target: {}", path.display());
-            Err(())
+        match metadata {
+            Ok(metadata) => {
+                let root = metadata.workspace_root.as_std_path().to_path_buf();
+                // the configuration file takes precedence over the workspace metadata
+                let config = if let Some((_, config)) =
+                    config_file.filter(|(path, _)| path.starts_with(&root))
+                {
+                    config?
+                } else if let Some(value) = metadata.workspace_metadata.get("rustowl") {
+                    Config::from_value(value.clone(), &root).map_err(|message| ConfigError {
+                        path: metadata
+                            .workspace_root
+                            .join("Cargo.toml")
+                            .into_std_path_buf(),
+                        message,
+                    })?
+                } else {
+                    Config::default()
+                };
+                let cargo = cargo.union(config.cargo);
+                if cargo.offline {
+                    log::info!("cargo runs offline");
+                }
+                Ok(Self {
+                    path: root,
+                    metadata: Some(metadata),
+                    rust_project: None,
+                    script,
+                    build_targets,
+                    config,
+                    cargo,
+                })
+            }
+            Err(_) if path.is_file() && path.extension().map(|v| v == "rs").unwrap_or(false) => {
+                let config = load_config(dir, None)?.unwrap_or_default();
+                Ok(Self {
+                    path,
+                    metadata: None,
+                    rust_project: None,
+                    script: None,
+                    build_targets: Vec::new(),
+                    cargo: cargo.union(config.cargo),
+                    config,
+                })
+            }
+            Err(_) if !dir.ancestors().any(|v| v.join("Cargo.toml").is_file()) => {
+                Err(AnalyzerError::NoCargoToml(path))
+            }
+            Err(message) => Err(AnalyzerError::CargoMetadata { path, message }),
         }
     }
     pub fn target_path(&self) -> &Path {
-        &self.path
+        // scripts in the same directory share the workspace root
+        self.script.as_deref().unwrap_or(&self.path)
     }
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    fn cache_dir(&self, target_dir: &Path) -> PathBuf {
+        self.config
+            .cache_dir
+            .clone()
+            .unwrap_or_else(|| cache_dir(target_dir))
+    }
+
     pub fn workspace_path(&self) -> Option<&Path> {
-        if self.metadata.is_some() {
+        if self.metadata.is_some() || self.rust_project.is_some() {
             Some(&self.path)
         } else {
             None
         }
     }
 
-    pub async fn analyze(&self, all_targets: bool, all_features: bool) -> AnalyzeEventIter {
+    /// Find the workspace package target that the source file belongs to.
+    ///
+    /// A target whose root module is the file itself is preferred; otherwise, the target
+    /// whose root module directory is the nearest ancestor of the file is selected.
+    pub fn package_target(&self, file: impl AsRef<Path>) -> Option<PackageTarget> {
+        let file = file.as_ref();
+        let metadata = self.metadata.as_ref()?;
+        let mut selected: Option<(usize, PackageTarget)> = None;
+        for package in metadata
+            .workspace_packages()
+            .into_iter()
+            .filter(|v| !self.config.is_package_excluded(&v.name))
+        {
+            let package_root = package.manifest_path.parent()?.as_std_path();
+            if !file.starts_with(package_root) {
+                continue;
+            }
+            for target in &package.targets {
+                if target.is_custom_build() {
+                    continue;
+                }
+                let src_path = target.src_path.as_std_path();
+                let score = if src_path == file {
+                    usize::MAX
+                } else if let Some(src_dir) = src_path.parent()
+                    && file.starts_with(src_dir)
+                {
+                    src_dir.components().count()
+                } else {
+                    continue;
+                };
+                if selected.as_ref().map(|v| v.0 < score).unwrap_or(true) {
+                    selected = Some((score, PackageTarget::from_target(package, target)));
+                }
+            }
+        }
+        selected.map(|v| v.1)
+    }
+
+    /// Find the dependency package that the source file belongs to.
+    ///
+    /// Files of workspace packages are not considered dependencies.
+    pub fn dependency_package(&self, file: impl AsRef<Path>) -> Option<DependencyPackage> {
+        let file = file.as_ref();
+        let metadata = self.metadata.as_ref()?;
+        if self.package_target(file).is_some() {
+            return None;
+        }
+        metadata
+            .packages
+            .iter()
+            .filter(|v| !metadata.workspace_members.contains(&v.id))
+            .filter_map(|v| Some((v, v.manifest_path.parent()?.as_std_path())))
+            .filter(|(_, dir)| file.starts_with(dir))
+            // the nearest package, since a package may contain others
+            .max_by_key(|(_, dir)| dir.components().count())
+            .map(|(package, dir)| DependencyPackage {
+                name: package.name.to_string(),
+                version: package.version.to_string(),
+                manifest_dir: dir.to_path_buf(),
+            })
+    }
+
+    /// Target triples passed to `cargo check --target`, where `None` is the default target
+    fn target_triples(&self, options: &AnalyzeOptions) -> Vec<Option<String>> {
+        if !options.targets.is_empty() {
+            options.targets.iter().cloned().map(Some).collect()
+        } else if !self.config.targets.is_empty() {
+            self.config.targets.iter().cloned().map(Some).collect()
+        } else if !self.build_targets.is_empty() {
+            self.build_targets.iter().cloned().map(Some).collect()
+        } else {
+            vec![None]
+        }
+    }
+
+    pub async fn analyze(
+        &self,
+        options: &AnalyzeOptions,
+    ) -> Result<AnalyzeEventIter, AnalyzerError> {
         if let Some(metadata) = &self.metadata {
-            self.analyze_package(metadata, all_targets, all_features)
+            self.analyze_package(metadata, None, None, options).await
+        } else if let Some(rust_project) = &self.rust_project {
+            self.analyze_rust_project(rust_project, options).await
+        } else {
+            self.analyze_single_file(&self.path, options).await
+        }
+    }
+
+    /// Whether the workspace has packages to analyze other than the package
+    pub fn has_packages_except(&self, package: &str) -> bool {
+        self.metadata.as_ref().is_some_and(|metadata| {
+            metadata
+                .workspace_packages()
+                .iter()
+                .any(|v| v.name != package && !self.config.is_package_excluded(&v.name))
+        })
+    }
+
+    /// Analyze the workspace except for the package, which has been analyzed already.
+    pub async fn analyze_except(
+        &self,
+        package: &str,
+        options: &AnalyzeOptions,
+    ) -> Result<AnalyzeEventIter, AnalyzerError> {
+        let mut analyzer = self.clone();
+        analyzer.config.exclude_packages.push(package.to_owned());
+        analyzer.analyze(options).await
+    }
+
+    /// Analyze only the specified package target instead of the whole workspace.
+    pub async fn analyze_target(
+        &self,
+        target: &PackageTarget,
+        options: &AnalyzeOptions,
+    ) -> Result<AnalyzeEventIter, AnalyzerError> {
+        if let Some(metadata) = &self.metadata {
+            self.analyze_package(metadata, Some(target), None, options)
                 .await
+        } else if let Some(rust_project) = &self.rust_project {
+            self.analyze_rust_project(rust_project, options).await
         } else {
-            self.analyze_single_file(&self.path).await
+            self.analyze_single_file(&self.path, options).await
         }
     }
 
+    /// Analyze a dependency package on demand.
+    ///
+    /// The dependency is checked in its own target directory, so that its results are
+    /// cached separately from the workspace.
+    pub async fn analyze_dependency(
+        &self,
+        dependency: &DependencyPackage,
+        options: &AnalyzeOptions,
+    ) -> Result<AnalyzeEventIter, AnalyzerError> {
+        let Some(metadata) = &self.metadata else {
+            return Err(AnalyzerError::NoCargoToml(self.path.clone()));
+        };
+        let target = dependency.package_target();
+        self.analyze_package(metadata, Some(&target), Some(dependency), options)
+            .await
+    }
+
     async fn analyze_package(
         &self,
         metadata: &cargo_metadata::Metadata,
-        all_targets: bool,
-        all_features: bool,
-    ) -> AnalyzeEventIter {
-        let package_names: Vec<_> = metadata
-            .workspace_packages()
+        target: Option<&PackageTarget>,
+        dependency: Option<&DependencyPackage>,
+        options: &AnalyzeOptions,
+    ) -> Result<AnalyzeEventIter, AnalyzerError> {
+        // `--all-targets` is meaningless when the target is specified
+        let all_targets = target.is_none() && options.all_targets;
+        let package_names: Vec<_> = if let Some(target) = target {
+            vec![target.package.clone()]
+        } else {
+            metadata
+                .workspace_packages()
+                .iter()
+                .map(|v| v.name.to_string())
+                .filter(|v| !self.config.is_package_excluded(v))
+                .collect()
+        };
+        let mut target_dir = metadata.target_directory.as_std_path().join("owl");
+        if let Some(dependency) = dependency {
+            target_dir = target_dir
+                .join("dependencies")
+                .join(format!("{}-{}", dependency.name, dependency.version));
+        }
+        let result_dir = result_dir(&target_dir);
+        // Units which cargo considers fresh are not passed to rustowlc again, and their
+        // results are replayed from the result directory instead.
+        // Units built before results were stored have nothing to replay, so we clean them.
+        if !result_dir.is_dir() {
+            log::debug!("clear cargo cache");
+            for package_name in &package_names {
+                let mut command = toolchain::setup_cargo_command().await?;
+                command
+                    .args(["clean", "--package", package_name])
+                    .args(script_args(self.script.as_deref()))
+                    .args(self.cargo.args())
+                    .env("CARGO_TARGET_DIR", &target_dir)
+                    .current_dir(&self.path)
+                    .stdout(std::process::Stdio::null())
+                    .stderr(std::process::Stdio::null());
+                let status = command
+                    .spawn()
+                    .map_err(AnalyzerError::command("cargo clean"))?
+                    .wait()
+                    .await
+                    .map_err(AnalyzerError::command("cargo clean"))?;
+                // stale units would be considered fresh with no results to replay
+                if !status.success() {
+                    return Err(AnalyzerError::command("cargo clean")(
+                        std::io::Error::other(format!("exited with {status}")),
+                    ));
+                }
+            }
+            if let Err(e) = tokio::fs::create_dir_all(&result_dir).await {
+                log::warn!("failed to create result dir: {e}");
+            }
+        }
+
+        let triples = self.target_triples(options);
+        let mut commands = Vec::with_capacity(triples.len());
+        for triple in &triples {
+            let command = self
+                .check_command(target, dependency, options, &target_dir, triple.as_deref())
+                .await?;
+            // run again for the units whose stored results cannot be replayed
+            let retry = self
+                .check_command(target, dependency, options, &target_dir, triple.as_deref())
+                .await?;
+            let triple = triple
+                .clone()
+                .unwrap_or_else(|| toolchain::HOST_TUPLE.to_owned());
+            commands.push((triple, command, retry));
+        }
+
+        let roots: Vec<_> = if let Some(dependency) = dependency {
+            metadata
+                .packages
+                .iter()
+                .filter(|v| {
+                    v.manifest_path.parent().map(|v| v.as_std_path())
+                        == Some(dependency.manifest_dir.as_path())
+                })
+                .collect()
+        } else {
+            metadata
+                .workspace_packages()
+                .into_iter()
+                .filter(|v| package_names.contains(&v.name.to_string()))
+                .collect()
+        };
+        // units are compiled for each target
+        let unit_count =
+            estimate_unit_count(metadata, &roots, target, all_targets) * commands.len();
+        log::debug!("{unit_count} units are estimated to be compiled");
+        let workspace_members: HashSet<_> = metadata
+            .workspace_members
             .iter()
-            .map(|v| v.name.to_string())
+            .map(|v| v.repr.clone())
             .collect();
-        let target_dir = metadata.target_directory.as_std_path().join("owl");
-        log::debug!("clear cargo cache");
-        for package_name in &package_names {
-            let mut command = toolchain::setup_cargo_command().await;
-            command
-                .args(["clean", "--package", package_name])
-                .env("CARGO_TARGET_DIR", &target_dir)
-                .current_dir(&self.path)
-                .stdout(std::process::Stdio::null())
-                .stderr(std::process::Stdio::null());
-            command.spawn().unwrap().wait().await.ok();
-        }
 
-        let mut command = toolchain::setup_cargo_command().await;
+        // packages whose units store results to be replayed
+        let analyzed_packages: HashSet<_> = roots.iter().map(|v| v.id.repr.clone()).collect();
 
-        let mut args = vec!["check", "--workspace"];
-        if all_targets {
-            args.push("--all-targets");
+        log::debug!("start analyzing package {package_names:?}");
+        let mut commands = commands.into_iter();
+        let (triple, mut command, retry) = commands.next().unwrap();
+        let child = command
+            .spawn()
+            .map_err(AnalyzerError::command("cargo check"))?;
+
+        let workspace_root = self.path.clone();
+        let (sender, receiver) = mpsc::channel(1024);
+        let notify = Arc::new(Notify::new());
+        let notify_c = notify.clone();
+        // targets are checked one after another to tell which target the results are for
+        let task = tokio::spawn(async move {
+            let mut next: Option<(_, _, _, Option<HashSet<String>>)> =
+                Some((triple, child, Some(retry), None));
+            while let Some((triple, mut child, retry, rechecked)) = next.take() {
+                log::debug!("check for target {triple}");
+                // crates whose stored results cannot be replayed, checked again after this pass
+                let mut stale = HashSet::new();
+                let mut stdout = BufReader::new(child.stdout.take().unwrap()).lines();
+                while let Ok(Some(line)) = stdout.next_line().await {
+                    if let Ok(CargoCheckMessage::CompilerArtifact {
+                        package_id,
+                        target,
+                        fresh,
+                        filenames,
+                    }) = serde_json::from_str(&line)
+                    {
+                        // units have been reported by the first pass
+                        if rechecked.is_some() {
+                            continue;
+                        }
+                        let checked = target.name;
+                        log::debug!("crate {checked} checked");
+
+                        if fresh {
+                            match replay_results(&result_dir, &filenames).await {
+                                Some(workspaces) => {
+                                    for workspace in workspaces {
+                                        log::debug!("replay results of {checked}");
+                                        let event = AnalyzerEvent::Analyzed {
+                                            triple: triple.clone(),
+                                            workspace,
+                                        };
+                                        let _ = sender.send(event).await;
+                                    }
+                                }
+                                None if analyzed_packages.contains(&package_id) => {
+                                    log::info!(
+                                        "stored results of {checked} are not available; check again"
+                                    );
+                                    // cargo checks a unit again when its output is missing
+                                    for rmeta in filenames.iter().filter(|v| is_rmeta(v)) {
+                                        tokio::fs::remove_file(rmeta).await.ok();
+                                    }
+                                    stale.insert(checked.replace('-', "_"));
+                                }
+                                None => {}
+                            }
+                        }
+
+                        let event = AnalyzerEvent::CrateChecked {
+                            package: checked,
+                            workspace_member: workspace_members.contains(&package_id),
+                            unit_count,
+                        };
+                        let _ = sender.send(event).await;
+                        continue;
+                    }
+                    if let Ok(CargoCheckMessage::CompilerMessage {
+                        target,
+                        mut message,
+                    }) = serde_json::from_str(&line)
+                    {
+                        // diagnostics of units which compile are replayed by the first pass
+                        if rechecked.is_some() {
+                            continue;
+                        }
+                        // file names are relative to the workspace root
+                        for span in &mut message.spans {
+                            span.file_name = workspace_root.join(&span.file_name);
+                        }
+                        let event = AnalyzerEvent::Diagnostic {
+                            package: target.name,
+                            message,
+                        };
+                        let _ = sender.send(event).await;
+                        continue;
+                    }
+                    if let Ok(mut workspace) = serde_json::from_str::<Workspace>(&line) {
+                        // dependents of the checked crates are analyzed again as well
+                        if let Some(rechecked) = &rechecked {
+                            workspace.0.retain(|name, _| rechecked.contains(name));
+                        }
+                        if workspace.0.is_empty() {
+                            continue;
+                        }
+                        let event = AnalyzerEvent::Analyzed {
+                            triple: triple.clone(),
+                            workspace,
+                        };
+                        let _ = sender.send(event).await;
+                        continue;
+                    }
+                    if !line.is_empty() {
+                        log::warn!("unknown format stdout from rustowlc");
+                    }
+                }
+                let _ = child.wait().await;
+
+                if !stale.is_empty()
+                    && let Some(mut command) = retry
+                {
+                    match command.spawn() {
+                        Ok(child) => {
+                            next = Some((triple, child, None, Some(stale)));
+                            continue;
+                        }
+                        Err(e) => log::error!("failed to run cargo check: {e}"),
+                    }
+                }
+                if let Some((triple, mut command, retry)) = commands.next() {
+                    match command.spawn() {
+                        Ok(child) => next = Some((triple, child, Some(retry), None)),
+                        Err(e) => log::error!("failed to run cargo check: {e}"),
+                    }
+                }
+            }
+            log::debug!("stdout closed");
+            notify_c.notify_one();
+        });
+
+        Ok(AnalyzeEventIter {
+            receiver,
+            notify,
+            child: None,
+            task: Some(task.abort_handle()),
+        })
+    }
+
+    /// `cargo check` running rustowlc for the target triple, or the host if not specified
+    async fn check_command(
+        &self,
+        target: Option<&PackageTarget>,
+        dependency: Option<&DependencyPackage>,
+        options: &AnalyzeOptions,
+        target_dir: &Path,
+        triple: Option<&str>,
+    ) -> Result<process::Command, AnalyzerError> {
+        // `--all-targets` is meaningless when the target is specified
+        let all_targets = target.is_none() && options.all_targets;
+        let mut command = toolchain::setup_cargo_command().await?;
+
+        let mut args = vec!["check".to_owned()];
+        args.extend(script_args(self.script.as_deref()));
+        args.extend(self.cargo.args().into_iter().map(str::to_owned));
+        if let Some(target) = target {
+            args.extend(target.cargo_args());
+        } else {
+            args.push("--workspace".to_owned());
+            for package in &self.config.exclude_packages {
+                args.extend(["--exclude".to_owned(), package.clone()]);
+            }
+            if all_targets {
+                args.push("--all-targets".to_owned());
+            }
+        }
+        if let Some(triple) = triple {
+            args.extend(["--target".to_owned(), triple.to_owned()]);
+        }
+        // features of packages outside of the workspace cannot be specified
+        if dependency.is_none() {
+            if options.all_features || self.config.all_features {
+                args.push("--all-features".to_owned());
+            } else if !self.config.features.is_empty() {
+                args.push(format!("--features={}", self.config.features.join(",")));
+            }
         }
-        if all_features {
-            args.push("--all-features");
+        if let Some(jobs) = options.jobs {
+            args.push(format!("--jobs={jobs}"));
         }
-        args.extend_from_slice(&["--keep-going", "--message-format=json"]);
+        args.extend([
+            "--keep-going".to_owned(),
+            "--message-format=json".to_owned(),
+        ]);
 
         command
             .args(args)
-            .env("CARGO_TARGET_DIR", &target_dir)
+            .env("CARGO_TARGET_DIR", target_dir)
             .env_remove("RUSTC_WRAPPER")
             .current_dir(&self.path)
             .stdout(std::process::Stdio::piped())
             .kill_on_drop(true);
 
+        set_result_path(&mut command, target_dir);
+        options.budget.set_env(&mut command);
+        self.config.set_env(&mut command);
         if is_cache() {
-            set_cache_path(&mut command, target_dir);
+            set_cache_path(&mut command, self.cache_dir(target_dir));
+        }
+        if let Some(dependency) = dependency {
+            set_dependency_path(&mut command, &dependency.manifest_dir);
         }
 
         if log::max_level()
@@ -153,69 +878,150 @@ impl Analyzer {
         {
             command.stderr(std::process::Stdio::null());
         }
+        Ok(command)
+    }
 
-        let package_count = metadata.packages.len();
-
-        log::debug!("start analyzing package {package_names:?}");
-        let mut child = command.spawn().unwrap();
-        let mut stdout = BufReader::new(child.stdout.take().unwrap()).lines();
+    /// Run rustowlc for each crate of `rust-project.json` in dependency order
+    async fn analyze_rust_project(
+        &self,
+        rust_project: &RustProject,
+        options: &AnalyzeOptions,
+    ) -> Result<AnalyzeEventIter, AnalyzerError> {
+        if !options.targets.is_empty() {
+            log::warn!("target selection is only supported for Cargo projects");
+        }
+        let sysroot = toolchain::get_sysroot().await?;
+        let rustowlc_path = toolchain::get_executable_path("rustowlc").await?;
+        let target_dir = rust_project.root().join("target").join("owl");
+        let out_dir = target_dir.join("rust-project");
+        if let Err(e) = tokio::fs::create_dir_all(&out_dir).await {
+            log::warn!("failed to create output dir: {e}");
+        }
 
+        let rust_project = rust_project.clone();
+        let budget = options.budget;
+        let config = self.config.clone();
+        let cache_dir = self.cache_dir(&target_dir);
         let (sender, receiver) = mpsc::channel(1024);
         let notify = Arc::new(Notify::new());
         let notify_c = notify.clone();
-        let _handle = tokio::spawn(async move {
-            // prevent command from dropped
-            while let Ok(Some(line)) = stdout.next_line().await {
-                if let Ok(CargoCheckMessage::CompilerArtifact { target }) =
-                    serde_json::from_str(&line)
-                {
-                    let checked = target.name;
-                    log::debug!("crate {checked} checked");
+        let task = tokio::spawn(async move {
+            let unit_count = rust_project.build_order().len();
+            for &index in rust_project.build_order() {
+                let workspace_member = rust_project.is_workspace_member(index)
+                    && !config.is_package_excluded(&rust_project.crate_name(index));
+                if !workspace_member && !rust_project.needs_build(index) {
+                    continue;
+                }
+                let crate_name = rust_project.crate_name(index);
 
-                    let event = AnalyzerEvent::CrateChecked {
-                        package: checked,
-                        package_count,
-                    };
-                    let _ = sender.send(event).await;
+                let mut command = process::Command::new(&rustowlc_path);
+                // rustowlc triggers when first arg is the path of itself
+                if workspace_member {
+                    command.arg(&rustowlc_path);
                 }
-                if let Ok(ws) = serde_json::from_str::<Workspace>(&line) {
-                    let event = AnalyzerEvent::Analyzed(ws);
-                    let _ = sender.send(event).await;
+                command
+                    .arg(format!("--sysroot={}", sysroot.display()))
+                    .args(rust_project.rustc_args(index, &out_dir))
+                    .envs(rust_project.env(index))
+                    .current_dir(rust_project.root())
+                    .stdout(std::process::Stdio::piped())
+                    .stderr(std::process::Stdio::piped())
+                    .kill_on_drop(true);
+                toolchain::set_rustc_env(&mut command, &sysroot);
+                budget.set_env(&mut command);
+                config.set_env(&mut command);
+                if is_cache() {
+                    set_cache_path(&mut command, &cache_dir);
                 }
-                if !line.is_empty() {
-                    log::warn!("unknown format stdout from rustowlc");
+
+                log::debug!("start analyzing crate {crate_name}");
+                let mut child = match command.spawn() {
+                    Ok(v) => v,
+                    Err(e) => {
+                        log::error!("failed to run {rustowlc_path}: {e}");
+                        break;
+                    }
+                };
+                let mut stdout = BufReader::new(child.stdout.take().unwrap()).lines();
+                let mut stderr = BufReader::new(child.stderr.take().unwrap()).lines();
+
+                // diagnostics are emitted to stderr in JSON format
+                let root = rust_project.root().to_path_buf();
+                let diagnostic_sender = sender.clone();
+                let package = crate_name.clone();
+                let diagnostics = tokio::spawn(async move {
+                    while let Ok(Some(line)) = stderr.next_line().await {
+                        if let Ok(mut message) = serde_json::from_str::<CompilerMessage>(&line) {
+                            for span in &mut message.spans {
+                                span.file_name = root.join(&span.file_name);
+                            }
+                            let event = AnalyzerEvent::Diagnostic {
+                                package: package.clone(),
+                                message,
+                            };
+                            let _ = diagnostic_sender.send(event).await;
+                        } else if log::max_level() >= log::LevelFilter::Info {
+                            eprintln!("{line}");
+                        }
+                    }
+                });
+                while let Ok(Some(line)) = stdout.next_line().await {
+                    if let Ok(workspace) = serde_json::from_str::<Workspace>(&line) {
+                        let event = AnalyzerEvent::Analyzed {
+                            triple: toolchain::HOST_TUPLE.to_owned(),
+                            workspace,
+                        };
+                        let _ = sender.send(event).await;
+                    }
                 }
+                let _ = diagnostics.await;
+                let _ = child.wait().await;
+
+                let event = AnalyzerEvent::CrateChecked {
+                    package: crate_name,
+                    workspace_member,
+                    unit_count,
+                };
+                let _ = sender.send(event).await;
             }
-            log::debug!("stdout closed");
+            log::debug!("all crates analyzed");
             notify_c.notify_one();
         });
 
-        AnalyzeEventIter {
+        Ok(AnalyzeEventIter {
             receiver,
             notify,
-            child,
-        }
+            child: None,
+            task: Some(task.abort_handle()),
+        })
     }
 
-    async fn analyze_single_file(&self, path: &Path) -> AnalyzeEventIter {
-        let sysroot = toolchain::get_sysroot().await;
-        let rustowlc_path = toolchain::get_executable_path("rustowlc").await;
+    async fn analyze_single_file(
+        &self,
+        path: &Path,
+        options: &AnalyzeOptions,
+    ) -> Result<AnalyzeEventIter, AnalyzerError> {
+        if !options.targets.is_empty() {
+            log::warn!("target selection is only supported for Cargo projects");
+        }
+        let sysroot = toolchain::get_sysroot().await?;
+        let rustowlc_path = toolchain::get_executable_path("rustowlc").await?;
+
+        let source = tokio::fs::read_to_string(path).await.unwrap_or_default();
+        let edition = single_file_edition(path).await;
 
         let mut command = process::Command::new(&rustowlc_path);
         command
             .arg(&rustowlc_path) // rustowlc triggers when first arg is the path of itself
             .arg(format!("--sysroot={}", sysroot.display()))
-            .arg("--crate-type=lib");
-        #[cfg(unix)]
-        command.arg("-o/dev/null");
-        #[cfg(windows)]
-        command.arg("-oNUL");
-        command
-            .arg(path)
+            .args(single_file::rustc_args(path, &source, &edition))
             .stdout(std::process::Stdio::piped())
             .kill_on_drop(true);
 
         toolchain::set_rustc_env(&mut command, &sysroot);
+        options.budget.set_env(&mut command);
+        self.config.set_env(&mut command);
 
         if log::max_level()
             .to_level()
@@ -226,7 +1032,9 @@ impl Analyzer {
         }
 
         log::debug!("start analyzing {}", path.display());
-        let mut child = command.spawn().unwrap();
+        let mut child = command
+            .spawn()
+            .map_err(AnalyzerError::command(&rustowlc_path))?;
         let mut stdout = BufReader::new(child.stdout.take().unwrap()).lines();
 
         let (sender, receiver) = mpsc::channel(1024);
@@ -235,8 +1043,11 @@ impl Analyzer {
         let _handle = tokio::spawn(async move {
             // prevent command from dropped
             while let Ok(Some(line)) = stdout.next_line().await {
-                if let Ok(ws) = serde_json::from_str::<Workspace>(&line) {
-                    let event = AnalyzerEvent::Analyzed(ws);
+                if let Ok(workspace) = serde_json::from_str::<Workspace>(&line) {
+                    let event = AnalyzerEvent::Analyzed {
+                        triple: toolchain::HOST_TUPLE.to_owned(),
+                        workspace,
+                    };
                     let _ = sender.send(event).await;
                 }
             }
@@ -244,19 +1055,176 @@ impl Analyzer {
             notify_c.notify_one();
         });
 
-        AnalyzeEventIter {
+        Ok(AnalyzeEventIter {
             receiver,
             notify,
-            child,
+            child: Some(child),
+            task: None,
+        })
+    }
+}
+
+/// Edition of the single file, which is taken from the nearest rustfmt configuration
+async fn single_file_edition(path: &Path) -> String {
+    for dir in path.ancestors().skip(1) {
+        for name in ["rustfmt.toml", ".rustfmt.toml"] {
+            if let Ok(config) = tokio::fs::read_to_string(dir.join(name)).await {
+                return single_file::rustfmt_edition(&config)
+                    .unwrap_or_else(|| single_file::DEFAULT_EDITION.to_owned());
+            }
+        }
+    }
+    single_file::DEFAULT_EDITION.to_owned()
+}
+
+/// Arguments to make cargo commands operate on the cargo script
+fn script_args(script: Option<&Path>) -> Vec<String> {
+    if let Some(script) = script {
+        vec![
+            "-Zscript".to_owned(),
+            "--manifest-path".to_owned(),
+            script.display().to_string(),
+        ]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Estimate the number of units `cargo check` compiles for the root packages
+///
+/// Every package reachable from the roots in the resolve graph contributes its library
+/// and build script, and the roots contribute the targets selected by the options.
+/// `cargo metadata` is filtered by platform, so the resolve graph only contains
+/// dependencies which are actually built.
+fn estimate_unit_count(
+    metadata: &cargo_metadata::Metadata,
+    roots: &[&cargo_metadata::Package],
+    target: Option<&PackageTarget>,
+    all_targets: bool,
+) -> usize {
+    use cargo_metadata::{DependencyKind, TargetKind};
+
+    let has_kind = |package: &cargo_metadata::Package, kinds: &[TargetKind]| {
+        package
+            .targets
+            .iter()
+            .filter(|v| kinds.iter().any(|kind| v.is_kind(kind.clone())))
+            .count()
+    };
+    let lib_kinds = [
+        TargetKind::Lib,
+        TargetKind::RLib,
+        TargetKind::DyLib,
+        TargetKind::ProcMacro,
+    ];
+    let build_script =
+        |package: &cargo_metadata::Package| has_kind(package, &[TargetKind::CustomBuild]).min(1);
+
+    // test, example and bench targets need dev-dependencies
+    let dev_deps = all_targets
+        || matches!(
+            target.map(|v| &v.target),
+            Some(
+                TargetSelection::Test(_) | TargetSelection::Example(_) | TargetSelection::Bench(_)
+            )
+        );
+    let mut count = 0;
+    for root in roots {
+        let lib = has_kind(root, &lib_kinds).min(1);
+        count += build_script(root);
+        count += match target {
+            Some(PackageTarget {
+                target: TargetSelection::Lib,
+                ..
+            }) => lib,
+            // the library is compiled as a dependency of the other targets
+            Some(_) => 1 + lib,
+            None if all_targets => {
+                // libraries and binaries are also compiled as unit tests
+                let targets = root.targets.iter().filter(|v| !v.is_custom_build());
+                let unit_tests = root
+                    .targets
+                    .iter()
+                    .filter(|v| {
+                        v.test && (v.is_bin() || lib_kinds.iter().any(|k| v.is_kind(k.clone())))
+                    })
+                    .count();
+                targets.count() + unit_tests
+            }
+            None => lib + has_kind(root, &[TargetKind::Bin]),
+        };
+    }
+
+    let Some(resolve) = &metadata.resolve else {
+        return count + metadata.packages.len();
+    };
+    let nodes: HashMap<_, _> = resolve.nodes.iter().map(|v| (&v.id, v)).collect();
+    let packages: HashMap<_, _> = metadata.packages.iter().map(|v| (&v.id, v)).collect();
+    let root_ids: HashSet<_> = roots.iter().map(|v| &v.id).collect();
+
+    let mut visited = HashSet::new();
+    let mut stack: Vec<_> = root_ids.iter().map(|v| (*v, true)).collect();
+    while let Some((id, is_root)) = stack.pop() {
+        let Some(node) = nodes.get(id) else {
+            continue;
+        };
+        for dep in &node.deps {
+            let required = dep.dep_kinds.iter().any(|v| match v.kind {
+                DependencyKind::Development => is_root && dev_deps,
+                _ => true,
+            });
+            if required && !root_ids.contains(&dep.pkg) && visited.insert(&dep.pkg) {
+                stack.push((&dep.pkg, false));
+            }
+        }
+    }
+    for id in visited {
+        if let Some(package) = packages.get(id) {
+            count += has_kind(package, &lib_kinds).min(1) + build_script(package);
+        }
+    }
+    count
+}
+
+fn is_rmeta(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "rmeta")
+}
+
+/// Read stored results of a unit which was not re-analyzed
+///
+/// Returns `None` if the results are missing or cannot be parsed,
+/// e.g. when they were written by another version of RustOwl.
+async fn replay_results(result_dir: &Path, filenames: &[PathBuf]) -> Option<Vec<Workspace>> {
+    let mut results = Vec::new();
+    for rmeta in filenames.iter().filter(|v| is_rmeta(v)) {
+        let path = result_file(result_dir, rmeta)?;
+        let s = tokio::fs::read_to_string(&path).await.ok()?;
+        match serde_json::from_str(&s) {
+            Ok(ws) => results.push(ws),
+            Err(e) => {
+                log::warn!("failed to parse stored result {}: {e}", path.display());
+                return None;
+            }
         }
     }
+    Some(results)
 }
 
 pub struct AnalyzeEventIter {
     receiver: mpsc::Receiver<AnalyzerEvent>,
     notify: Arc<Notify>,
     #[allow(unused)]
-    child: process::Child,
+    child: Option<process::Child>,
+    /// Task running compiler processes one after another
+    task: Option<tokio::task::AbortHandle>,
+}
+impl Drop for AnalyzeEventIter {
+    fn drop(&mut self) {
+        // processes spawned by the task are killed on drop
+        if let Some(task) = &self.task {
+            task.abort();
+        }
+    }
 }
 impl AnalyzeEventIter {
     pub async fn next_event(&mut self) -> Option<AnalyzerEvent> {
@@ -0,0 +1,186 @@
+//! Locals held across `.await`, which are stored in the future while it is suspended
+
+use crate::{models::*, utils};
+
+/// Types which block other code while they are held, e.g. a lock
+const GUARD_TYPES: [&str; 8] = [
+    "std::sync::MutexGuard",
+    "std::sync::MappedMutexGuard",
+    "std::sync::RwLockReadGuard",
+    "std::sync::RwLockWriteGuard",
+    "std::sync::MappedRwLockReadGuard",
+    "std::sync::MappedRwLockWriteGuard",
+    "std::cell::Ref",
+    "std::cell::RefMut",
+];
+
+#[derive(Clone, Debug)]
+pub struct HeldLocal {
+    pub local: FnLocal,
+    pub name: String,
+    pub ty: String,
+    pub span: Range,
+    /// Ranges of the `.await` (or `yield`) the local is held across
+    pub awaits: Vec<Range>,
+}
+impl HeldLocal {
+    pub fn is_guard(&self) -> bool {
+        is_guard(&self.ty)
+    }
+}
+
+pub fn is_guard(ty: &str) -> bool {
+    GUARD_TYPES.iter().any(|v| {
+        ty.strip_prefix(v)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('<'))
+    })
+}
+
+/// Suspension points of the coroutine
+pub fn await_ranges(func: &Function) -> Vec<Range> {
+    func.basic_blocks
+        .iter()
+        .filter(|bb| matches!(bb.terminator.kind, MirTerminatorKind::Yield { .. }))
+        .filter_map(|bb| bb.terminator.range)
+        .collect()
+}
+
+/// User variables which may be initialized while the coroutine is suspended
+///
+/// Variables introduced by the `.await` desugaring itself are ignored.
+pub fn held_across_await(func: &Function) -> Vec<HeldLocal> {
+    let awaits = await_ranges(func);
+    if awaits.is_empty() {
+        return Vec::new();
+    }
+    let contains = |ranges: &[Range], target: Range| {
+        ranges
+            .iter()
+            .any(|v| *v == target || utils::is_super_range(*v, target))
+    };
+    func.decls
+        .iter()
+        .filter_map(|decl| match decl {
+            MirDecl::User {
+                local,
+                name,
+                span,
+                ty,
+                storage_range,
+                maybe_init_at,
+                ..
+            } => {
+                let held: Vec<_> = awaits
+                    .iter()
+                    .copied()
+                    .filter(|v| span.until() <= v.from())
+                    .filter(|v| contains(storage_range, *v) && contains(maybe_init_at, *v))
+                    .collect();
+                (!held.is_empty()).then(|| HeldLocal {
+                    local: *local,
+                    name: name.clone(),
+                    ty: ty.name.clone(),
+                    span: *span,
+                    awaits: held,
+                })
+            }
+            MirDecl::Other { .. } => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(from: u32, until: u32) -> Range {
+        Range::new(Loc(from), Loc(until)).unwrap()
+    }
+
+    fn place(id: u32) -> MirPlace {
+        MirPlace {
+            local: FnLocal::new(id, 0),
+            projection: Vec::new(),
+        }
+    }
+
+    fn suspend(range: Range) -> MirBasicBlock {
+        MirBasicBlock {
+            statements: Vec::new(),
+            terminator: MirTerminator {
+                kind: MirTerminatorKind::Yield {
+                    value: MirOperand::Constant,
+                    resume: BasicBlockId(0),
+                    resume_arg: place(0),
+                    drop: None,
+                },
+                range: Some(range),
+            },
+        }
+    }
+
+    fn decl(id: u32, span: Range, storage_range: Vec<Range>, maybe_init_at: Vec<Range>) -> MirDecl {
+        MirDecl::User {
+            local: FnLocal::new(id, 0),
+            name: format!("v{id}"),
+            span,
+            ty: MirType {
+                name: "std::vec::Vec<u8>".to_owned(),
+                reference: None,
+            },
+            lives: Vec::new(),
+            field_borrows: Vec::new(),
+            drop: true,
+            drop_range: Vec::new(),
+            definitely_live_at: Vec::new(),
+            maybe_init_at,
+            must_live_at: Vec::new(),
+            storage_range,
+        }
+    }
+
+    #[test]
+    fn held_across_awaits() {
+        let awaits = [range(10, 15), range(40, 45), range(70, 75)];
+        let func = Function {
+            fn_id: 0,
+            name: "f::{closure#0}".to_owned(),
+            basic_blocks: awaits.into_iter().map(suspend).collect(),
+            decls: vec![
+                // initialized until the end of the body
+                decl(1, range(0, 5), vec![range(0, 100)], vec![range(0, 100)]),
+                // moved between the first and the second await
+                decl(2, range(0, 5), vec![range(0, 100)], vec![range(0, 25)]),
+                // declared after the first await
+                decl(3, range(30, 35), vec![range(30, 100)], vec![range(30, 100)]),
+                // the scope ends before the first await
+                decl(4, range(0, 5), vec![range(0, 8)], vec![range(0, 8)]),
+            ],
+            closure: None,
+            fields: Vec::new(),
+            loans: Vec::new(),
+            conflicts: Vec::new(),
+            lifetimes: Vec::new(),
+        };
+        let held: Vec<_> = held_across_await(&func)
+            .into_iter()
+            .map(|v| (v.name, v.awaits))
+            .collect();
+        assert_eq!(
+            held,
+            vec![
+                ("v1".to_owned(), awaits.to_vec()),
+                ("v2".to_owned(), vec![awaits[0]]),
+                ("v3".to_owned(), vec![awaits[1], awaits[2]]),
+            ]
+        );
+    }
+
+    #[test]
+    fn guard_types() {
+        assert!(is_guard("std::sync::MutexGuard<'_, std::vec::Vec<u8>>"));
+        assert!(is_guard("std::cell::RefMut<'_, i32>"));
+        assert!(!is_guard("std::cell::RefCell<i32>"));
+        assert!(!is_guard("&std::sync::MutexGuard<'_, i32>"));
+    }
+}
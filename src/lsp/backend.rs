@@ -1,7 +1,13 @@
 use super::analyze::*;
-use crate::{lsp::*, models::*, utils};
+use crate::{
+    budget,
+    config::{CargoMode, DecorationConfig},
+    lsp::*,
+    models::*,
+    utils,
+};
 use std::collections::BTreeMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::{sync::RwLock, task::JoinSet};
 use tokio_util::sync::CancellationToken;
@@ -15,16 +21,151 @@ pub struct AnalyzeRequest {}
 #[derive(serde::Serialize, Clone, Debug)]
 pub struct AnalyzeResponse {}
 
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct SelectTargetRequest {
+    /// Target triple whose results are shown
+    pub target: String,
+}
+#[derive(serde::Serialize, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct SelectTargetResponse {
+    /// Target triples which have results
+    pub targets: Vec<String>,
+    pub selected: Option<String>,
+}
+
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct ExplainBorrowResponse {
+    pub path: Option<PathBuf>,
+    /// From the creation of the borrow at the position to the use which keeps it live,
+    /// empty if no borrow is created there
+    pub steps: Vec<explain::BorrowStep<lsp_types::Range>>,
+}
+
+/// Options passed by the client in `initializationOptions`
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(rename_all = "snake_case", default)]
+pub struct InitializationOptions {
+    /// Continue analyzing the rest of the workspace
+    /// after the package of the opened document is analyzed
+    pub analyze_workspace: bool,
+    /// Maximum number of analysis jobs run at once
+    pub max_concurrent_jobs: usize,
+    /// Number of compiler processes each job runs in parallel
+    pub cargo_jobs: Option<usize>,
+    /// Target triples to analyze for, instead of the default target
+    pub targets: Vec<String>,
+    #[serde(flatten)]
+    pub budget: budget::Budget,
+    #[serde(flatten)]
+    pub cargo: CargoMode,
+}
+impl Default for InitializationOptions {
+    fn default() -> Self {
+        Self {
+            analyze_workspace: true,
+            max_concurrent_jobs: 1,
+            cargo_jobs: None,
+            targets: Vec::new(),
+            budget: budget::Budget::default(),
+            cargo: CargoMode::default(),
+        }
+    }
+}
+impl InitializationOptions {
+    fn analyze_options(&self) -> AnalyzeOptions {
+        AnalyzeOptions {
+            jobs: self.cargo_jobs,
+            budget: self.budget,
+            targets: self.targets.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Analysis results of each target triple
+#[derive(Default, Debug)]
+pub struct AnalyzedTargets {
+    results: BTreeMap<String, Crate>,
+    selected: Option<String>,
+}
+impl AnalyzedTargets {
+    /// Results of the selected target, or of the first target if it is not analyzed
+    pub fn view(&self) -> Option<&Crate> {
+        self.selected
+            .as_ref()
+            .and_then(|v| self.results.get(v))
+            .or_else(|| self.results.values().next())
+    }
+    pub fn merge(&mut self, triple: String, krate: Crate) {
+        if let Some(results) = self.results.get_mut(&triple) {
+            results.merge(krate);
+        } else {
+            self.results.insert(triple, krate);
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.results.values().all(|v| v.0.is_empty())
+    }
+    /// Discard the results, keeping the selection
+    pub fn clear(&mut self) {
+        self.results.clear();
+    }
+}
+
+/// Publish the compiler errors and the variables held across `.await` of the file
+async fn publish_diagnostics(
+    client: &Client,
+    path: &Path,
+    messages: &BTreeMap<String, Vec<CompilerMessage>>,
+    analyzed: &AnalyzedTargets,
+    decoration: &DecorationConfig,
+) {
+    let Ok(uri) = lsp_types::Url::from_file_path(path) else {
+        return;
+    };
+    let mut diagnostics = diagnostic::compiler_errors(messages.values().flatten())
+        .remove(path)
+        .unwrap_or_default();
+    if !decoration.is_hidden("held_across_await")
+        && let Some(file) = analyzed
+            .view()
+            .and_then(|v| v.0.get(path.to_string_lossy().as_ref()))
+        && let Ok(source) = std::fs::read_to_string(path)
+    {
+        diagnostics.extend(diagnostic::held_across_await(&uri, &source, file));
+    }
+    client.publish_diagnostics(uri, diagnostics, None).await;
+}
+
+/// Part of a workspace analyzed by a job
+enum Selection {
+    Workspace,
+    /// The workspace except for the package analyzed first
+    WorkspaceExcept(String),
+    Package(PackageTarget),
+    Dependency(DependencyPackage),
+}
+
 /// RustOwl LSP server backend
 pub struct Backend {
     #[allow(unused)]
     client: Client,
     analyzers: Arc<RwLock<Vec<Analyzer>>>,
     status: Arc<RwLock<progress::AnalysisStatus>>,
-    analyzed: Arc<RwLock<Option<Crate>>>,
+    analyzed: Arc<RwLock<AnalyzedTargets>>,
+    /// Results of dependencies analyzed on demand, which are kept while the workspace is edited
+    dependencies: Arc<RwLock<AnalyzedTargets>>,
     processes: Arc<RwLock<JoinSet<()>>>,
     process_tokens: Arc<RwLock<BTreeMap<usize, CancellationToken>>>,
     work_done_progress: Arc<RwLock<bool>>,
+    /// The most recently opened document, whose package is analyzed first
+    focused: Arc<RwLock<Option<PathBuf>>>,
+    options: Arc<RwLock<InitializationOptions>>,
+    /// Compiler diagnostics of the latest analysis, keyed by package
+    compiler_messages: Arc<RwLock<BTreeMap<String, Vec<CompilerMessage>>>>,
+    scheduler: Arc<scheduler::Scheduler>,
 }
 
 impl Backend {
@@ -32,27 +173,40 @@ impl Backend {
         Self {
             client,
             analyzers: Arc::new(RwLock::new(Vec::new())),
-            analyzed: Arc::new(RwLock::new(None)),
+            analyzed: Arc::new(RwLock::new(AnalyzedTargets::default())),
+            dependencies: Arc::new(RwLock::new(AnalyzedTargets::default())),
             status: Arc::new(RwLock::new(progress::AnalysisStatus::Finished)),
             processes: Arc::new(RwLock::new(JoinSet::new())),
             process_tokens: Arc::new(RwLock::new(BTreeMap::new())),
             work_done_progress: Arc::new(RwLock::new(false)),
+            focused: Arc::new(RwLock::new(None)),
+            options: Arc::new(RwLock::new(InitializationOptions::default())),
+            compiler_messages: Arc::new(RwLock::new(BTreeMap::new())),
+            scheduler: Arc::new(scheduler::Scheduler::new(
+                InitializationOptions::default().max_concurrent_jobs,
+            )),
         }
     }
 
-    async fn add_analyze_target(&self, path: &Path) -> bool {
-        if let Ok(new_analyzer) = Analyzer::new(&path).await {
-            let mut analyzers = self.analyzers.write().await;
-            for analyzer in &*analyzers {
-                if analyzer.target_path() == new_analyzer.target_path() {
-                    return true;
-                }
+    async fn add_analyze_target(&self, path: &Path) -> Result<(), AnalyzerError> {
+        let cargo = self.options.read().await.cargo;
+        let new_analyzer = Analyzer::new(&path, cargo).await?;
+        let mut analyzers = self.analyzers.write().await;
+        for analyzer in &*analyzers {
+            if analyzer.target_path() == new_analyzer.target_path() {
+                return Ok(());
             }
-            analyzers.push(new_analyzer);
-            true
-        } else {
-            false
         }
+        analyzers.push(new_analyzer);
+        Ok(())
+    }
+
+    /// Tell the user why the path cannot be analyzed
+    async fn report_analyzer_error(&self, e: AnalyzerError) {
+        log::error!("{e}");
+        self.client
+            .show_message(lsp_types::MessageType::ERROR, e.to_string())
+            .await;
     }
 
     pub async fn analyze(&self, _params: AnalyzeRequest) -> jsonrpc::Result<AnalyzeResponse> {
@@ -60,12 +214,25 @@ impl Backend {
         self.do_analyze().await;
         Ok(AnalyzeResponse {})
     }
+    pub async fn select_target(
+        &self,
+        params: SelectTargetRequest,
+    ) -> jsonrpc::Result<SelectTargetResponse> {
+        let mut analyzed = self.analyzed.write().await;
+        analyzed.selected = Some(params.target);
+        Ok(SelectTargetResponse {
+            targets: analyzed.results.keys().cloned().collect(),
+            selected: analyzed.selected.clone(),
+        })
+    }
+
     async fn do_analyze(&self) {
         self.shutdown_subprocesses().await;
-        self.analyze_with_options(false, false).await;
+        let options = self.options.read().await.analyze_options();
+        self.analyze_with_options(options).await;
     }
 
-    async fn analyze_with_options(&self, all_targets: bool, all_features: bool) {
+    async fn analyze_with_options(&self, options: AnalyzeOptions) {
         log::info!("wait 100ms for rust-analyzer");
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
 
@@ -76,13 +243,58 @@ impl Backend {
         {
             *self.status.write().await = progress::AnalysisStatus::Analyzing;
         }
+        self.clear_compiler_errors().await;
         let analyzers = { self.analyzers.read().await.clone() };
+        let focused = { self.focused.read().await.clone() };
+        let analyze_workspace = self.options.read().await.analyze_workspace;
 
         log::info!("analyze {} workspace(s)...", analyzers.len());
         for analyzer in analyzers {
+            // analyze the package of the focused document first,
+            // then the rest of the workspace
+            let mut selections = Vec::new();
+            if let Some(target) = focused.as_ref().and_then(|v| analyzer.package_target(v)) {
+                let package = target.package.clone();
+                selections.push((
+                    Selection::Package(target),
+                    scheduler::JobPriority::FocusedPackage,
+                ));
+                if analyze_workspace && analyzer.has_packages_except(&package) {
+                    selections.push((
+                        Selection::WorkspaceExcept(package),
+                        scheduler::JobPriority::FocusedWorkspace,
+                    ));
+                }
+            } else if let Some(focused) = &focused
+                && let Some(dependency) = analyzer.dependency_package(focused)
+            {
+                // dependencies do not change, so they are analyzed only once
+                if !self.is_dependency_analyzed(focused).await {
+                    selections.push((
+                        Selection::Dependency(dependency),
+                        scheduler::JobPriority::FocusedPackage,
+                    ));
+                }
+                selections.push((Selection::Workspace, scheduler::JobPriority::Background));
+            } else if focused
+                .as_ref()
+                .is_some_and(|v| v.starts_with(analyzer.target_path()))
+            {
+                selections.push((
+                    Selection::Workspace,
+                    scheduler::JobPriority::FocusedWorkspace,
+                ));
+            } else {
+                selections.push((Selection::Workspace, scheduler::JobPriority::Background));
+            }
+
             let analyzed = self.analyzed.clone();
+            let dependencies = self.dependencies.clone();
+            let compiler_messages = self.compiler_messages.clone();
             let client = self.client.clone();
             let work_done_progress = self.work_done_progress.clone();
+            let scheduler = self.scheduler.clone();
+            let options = options.clone();
             let cancellation_token = CancellationToken::new();
 
             let cancellation_token_key = {
@@ -102,43 +314,170 @@ impl Backend {
                 let mut progress_token = None;
                 if *work_done_progress.read().await {
                     progress_token =
-                        Some(progress::ProgressToken::begin(client, None::<&str>).await)
+                        Some(progress::ProgressToken::begin(client.clone(), None::<&str>).await)
                 };
 
-                let mut iter = analyzer.analyze(all_targets, all_features).await;
-                let mut analyzed_package_count = 0;
-                while let Some(event) = tokio::select! {
-                    _ = cancellation_token.cancelled() => None,
-                    event = iter.next_event() => event,
-                } {
-                    match event {
-                        AnalyzerEvent::CrateChecked {
-                            package,
-                            package_count,
-                        } => {
-                            analyzed_package_count += 1;
-                            if let Some(token) = &progress_token {
-                                let percentage =
-                                    (analyzed_package_count * 100 / package_count).min(100);
-                                token
-                                    .report(
-                                        Some(format!("{package} analyzed")),
-                                        Some(percentage as u32),
+                'selections: for (selection, priority) in selections {
+                    let queue = scheduler.state();
+                    if let Some(token) = &progress_token
+                        && queue.is_full()
+                    {
+                        token
+                            .report(Some(format!("waiting for other jobs ({queue})")), None)
+                            .await;
+                    }
+                    // the job slot is held until the analysis finishes
+                    let _permit = tokio::select! {
+                        _ = cancellation_token.cancelled() => break 'selections,
+                        permit = scheduler.acquire(priority) => permit,
+                    };
+
+                    let iter = match &selection {
+                        Selection::Package(target) => {
+                            log::info!("analyze package {} first", target.package);
+                            analyzer.analyze_target(target, &options).await
+                        }
+                        Selection::Dependency(dependency) => {
+                            log::info!("analyze dependency {}", dependency.name);
+                            analyzer.analyze_dependency(dependency, &options).await
+                        }
+                        Selection::Workspace => analyzer.analyze(&options).await,
+                        Selection::WorkspaceExcept(package) => {
+                            analyzer.analyze_except(package, &options).await
+                        }
+                    };
+                    let mut iter = match iter {
+                        Ok(v) => v,
+                        Err(e) => {
+                            log::error!("{e}");
+                            client
+                                .show_message(lsp_types::MessageType::ERROR, e.to_string())
+                                .await;
+                            break;
+                        }
+                    };
+                    let mut checked_unit_count = 0;
+                    let mut over_budget_count = 0;
+                    loop {
+                        let event = tokio::select! {
+                            _ = cancellation_token.cancelled() => break 'selections,
+                            event = iter.next_event() => event,
+                        };
+                        let Some(event) = event else {
+                            break;
+                        };
+                        match event {
+                            AnalyzerEvent::CrateChecked {
+                                package,
+                                workspace_member,
+                                unit_count,
+                            } => {
+                                checked_unit_count += 1;
+                                // the unit count is an estimate and may be exceeded
+                                let unit_count = unit_count.max(checked_unit_count);
+                                if let Some(token) = &progress_token {
+                                    let percentage = checked_unit_count * 100 / unit_count;
+                                    let mut message = if workspace_member {
+                                        format!("{package} analyzed")
+                                    } else {
+                                        format!("dependency {package} compiled")
+                                    };
+                                    let queued = scheduler.state().queued;
+                                    if 0 < queued {
+                                        message = format!("{message}; {queued} jobs queued");
+                                    }
+                                    token
+                                        .report(
+                                            Some(format!(
+                                                "{message} ({checked_unit_count}/{unit_count})"
+                                            )),
+                                            Some(percentage as u32),
+                                        )
+                                        .await;
+                                }
+                            }
+                            AnalyzerEvent::Analyzed { triple, workspace } => {
+                                over_budget_count += over_budget_items(&workspace).count();
+                                if let Selection::Dependency(_) = selection {
+                                    let mut write = dependencies.write().await;
+                                    for krate in workspace.0.into_values() {
+                                        write.merge(triple.clone(), krate);
+                                    }
+                                    continue;
+                                }
+                                let files: Vec<_> = workspace
+                                    .0
+                                    .values()
+                                    .flat_map(|v| v.0.keys())
+                                    .map(PathBuf::from)
+                                    .collect();
+                                {
+                                    let mut write = analyzed.write().await;
+                                    for krate in workspace.0.into_values() {
+                                        write.merge(triple.clone(), krate);
+                                    }
+                                }
+                                let messages = compiler_messages.read().await;
+                                let analyzed = analyzed.read().await;
+                                for path in files {
+                                    publish_diagnostics(
+                                        &client,
+                                        &path,
+                                        &messages,
+                                        &analyzed,
+                                        &analyzer.config().decoration,
                                     )
                                     .await;
+                                }
                             }
-                        }
-                        AnalyzerEvent::Analyzed(ws) => {
-                            let write = &mut *analyzed.write().await;
-                            for krate in ws.0.into_values() {
-                                if let Some(write) = write {
-                                    write.merge(krate);
-                                } else {
-                                    *write = Some(krate);
+                            AnalyzerEvent::Diagnostic { package, message } => {
+                                // the crate was aborted, which has no span to be published
+                                if message.code.as_ref().map(|v| v.code.as_str())
+                                    == Some(budget::BUDGET_EXCEEDED_CODE)
+                                {
+                                    client
+                                        .show_message(
+                                            lsp_types::MessageType::WARNING,
+                                            &message.message,
+                                        )
+                                        .await;
+                                }
+                                let mut messages = compiler_messages.write().await;
+                                let package_messages = messages.entry(package).or_default();
+                                if package_messages
+                                    .iter()
+                                    .any(|v| v.rendered == message.rendered)
+                                {
+                                    continue;
+                                }
+                                package_messages.push(message.clone());
+                                if !message.is_error() {
+                                    continue;
+                                }
+                                let analyzed = analyzed.read().await;
+                                for span in message.spans.iter().filter(|v| v.is_primary) {
+                                    publish_diagnostics(
+                                        &client,
+                                        &span.file_name,
+                                        &messages,
+                                        &analyzed,
+                                        &analyzer.config().decoration,
+                                    )
+                                    .await;
                                 }
                             }
                         }
                     }
+                    if 0 < over_budget_count {
+                        client
+                            .show_message(
+                                lsp_types::MessageType::WARNING,
+                                format!(
+                                    "{over_budget_count} functions were not analyzed within the budget"
+                                ),
+                            )
+                            .await;
+                    }
                 }
                 // remove cancellation token from list
                 process_tokens.write().await.remove(&cancellation_token_key);
@@ -157,7 +496,7 @@ impl Backend {
             let mut status = status.write().await;
             let analyzed = analyzed.write().await;
             if *status != progress::AnalysisStatus::Error {
-                if analyzed.as_ref().map(|v| v.0.len()).unwrap_or(0) == 0 {
+                if analyzed.is_empty() {
                     *status = progress::AnalysisStatus::Error;
                 } else {
                     *status = progress::AnalysisStatus::Finished;
@@ -166,6 +505,24 @@ impl Backend {
         });
     }
 
+    /// Clear compiler errors published by the previous analysis
+    async fn clear_compiler_errors(&self) {
+        let messages = std::mem::take(&mut *self.compiler_messages.write().await);
+        for path in diagnostic::compiler_errors(messages.values().flatten()).into_keys() {
+            if let Ok(uri) = lsp_types::Url::from_file_path(&path) {
+                self.client.publish_diagnostics(uri, Vec::new(), None).await;
+            }
+        }
+    }
+
+    async fn is_dependency_analyzed(&self, filepath: &Path) -> bool {
+        self.dependencies
+            .read()
+            .await
+            .view()
+            .is_some_and(|v| v.0.contains_key(filepath.to_string_lossy().as_ref()))
+    }
+
     async fn decos(
         &self,
         filepath: &Path,
@@ -173,28 +530,53 @@ impl Backend {
     ) -> Result<Vec<decoration::Deco>, progress::AnalysisStatus> {
         let mut selected = decoration::SelectLocal::new(position);
         let mut error = progress::AnalysisStatus::Error;
-        if let Some(analyzed) = &*self.analyzed.read().await {
-            for (filename, file) in analyzed.0.iter() {
+        let workspace = self.analyzed.read().await;
+        let dependencies = self.dependencies.read().await;
+        let crates: Vec<_> = workspace
+            .view()
+            .into_iter()
+            .chain(dependencies.view())
+            .collect();
+        if !crates.is_empty() {
+            for (filename, file) in crates.iter().flat_map(|v| &v.0) {
                 if &filepath.to_string_lossy() == filename {
-                    if !file.items.is_empty() {
+                    if file.items.iter().any(|v| v.function().is_some()) {
                         error = progress::AnalysisStatus::Finished;
                     }
-                    for item in &file.items {
+                    for item in file.items.iter().filter_map(Item::function) {
                         utils::mir_visit(item, &mut selected);
                     }
                 }
             }
 
-            let mut calc = decoration::CalcDecos::new(selected.selected().iter().copied());
-            for (filename, file) in analyzed.0.iter() {
+            let mut calc = match selected.selected_lifetime() {
+                Some((fn_id, name)) => {
+                    let mut calc = decoration::CalcDecos::new([]);
+                    calc.select_lifetime(fn_id, name);
+                    calc
+                }
+                None => decoration::CalcDecos::new(selected.selected().iter().copied()),
+            };
+            calc.set_cursor(position);
+            for (filename, file) in crates.iter().flat_map(|v| &v.0) {
                 if &filepath.to_string_lossy() == filename {
-                    for item in &file.items {
+                    for item in file.items.iter().filter_map(Item::function) {
                         utils::mir_visit(item, &mut calc);
                     }
                 }
             }
             calc.handle_overlapping();
-            let decos = calc.decorations();
+            let mut decos = calc.decorations();
+            if let Some(analyzer) = self
+                .analyzers
+                .read()
+                .await
+                .iter()
+                .find(|v| filepath.starts_with(v.target_path()))
+            {
+                let hidden = &analyzer.config().decoration;
+                decos.retain(|v| !hidden.is_hidden(v.kind()));
+            }
             if !decos.is_empty() {
                 Ok(decos)
             } else {
@@ -205,11 +587,52 @@ impl Backend {
         }
     }
 
+    pub async fn explain_borrow(
+        &self,
+        params: decoration::CursorRequest,
+    ) -> jsonrpc::Result<ExplainBorrowResponse> {
+        let Some(path) = params.path() else {
+            return Ok(ExplainBorrowResponse {
+                path: None,
+                steps: Vec::new(),
+            });
+        };
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            return Ok(ExplainBorrowResponse {
+                path: Some(path),
+                steps: Vec::new(),
+            });
+        };
+        let position = params.position();
+        let pos = Loc(utils::line_char_to_index(
+            &text,
+            position.line,
+            position.character,
+        ));
+
+        let workspace = self.analyzed.read().await;
+        let dependencies = self.dependencies.read().await;
+        let functions = workspace
+            .view()
+            .into_iter()
+            .chain(dependencies.view())
+            .flat_map(|v| &v.0)
+            .filter(|(filename, _)| path.to_string_lossy() == filename.as_str())
+            .flat_map(|(_, file)| file.items.iter().filter_map(Item::function));
+        let steps = explain::loan_at(functions, pos)
+            .map(|(func, loan)| explain::explain(func, loan))
+            .unwrap_or_default();
+        Ok(ExplainBorrowResponse {
+            path: Some(path),
+            steps: steps.iter().map(|v| v.to_lsp_range(&text)).collect(),
+        })
+    }
+
     pub async fn cursor(
         &self,
         params: decoration::CursorRequest,
     ) -> jsonrpc::Result<decoration::Decorations> {
-        let is_analyzed = self.analyzed.read().await.is_some();
+        let is_analyzed = self.analyzed.read().await.view().is_some();
         let status = *self.status.read().await;
         if let Some(path) = params.path()
             && let Ok(text) = std::fs::read_to_string(&path)
@@ -247,34 +670,88 @@ impl Backend {
         })
     }
 
-    pub async fn check(path: impl AsRef<Path>) -> bool {
-        Self::check_with_options(path, false, false).await
+    async fn symbols(&self, filepath: &Path, source: &str) -> Vec<lsp_types::DocumentSymbol> {
+        let workspace = self.analyzed.read().await;
+        let dependencies = self.dependencies.read().await;
+        for analyzed in workspace.view().into_iter().chain(dependencies.view()) {
+            for (filename, file) in analyzed.0.iter() {
+                if filepath.to_string_lossy() == *filename {
+                    return symbol::document_symbols(source, file);
+                }
+            }
+        }
+        Vec::new()
+    }
+
+    pub async fn check(path: impl AsRef<Path>) -> Result<bool, AnalyzerError> {
+        Self::check_with_options(path, AnalyzeOptions::default(), CargoMode::default()).await
     }
 
+    /// Analyze the path and return whether any result is produced
     pub async fn check_with_options(
         path: impl AsRef<Path>,
-        all_targets: bool,
-        all_features: bool,
-    ) -> bool {
+        options: AnalyzeOptions,
+        cargo: CargoMode,
+    ) -> Result<bool, AnalyzerError> {
         let path = path.as_ref();
         let (service, _) = LspService::build(Backend::new).finish();
         let backend = service.inner();
+        backend.options.write().await.cargo = cargo;
 
-        if backend.add_analyze_target(path).await {
-            backend
-                .analyze_with_options(all_targets, all_features)
-                .await;
-            while backend.processes.write().await.join_next().await.is_some() {}
-            backend
-                .analyzed
-                .read()
-                .await
-                .as_ref()
-                .map(|v| !v.0.is_empty())
-                .unwrap_or(false)
-        } else {
-            false
+        backend.add_analyze_target(path).await?;
+        backend.analyze_with_options(options).await;
+        while backend.processes.write().await.join_next().await.is_some() {}
+        for message in backend.compiler_messages.read().await.values().flatten() {
+            if message.is_error()
+                && let Some(rendered) = &message.rendered
+            {
+                eprint!("{rendered}");
+            }
+        }
+        let analyzed = backend.analyzed.read().await;
+        let mut statuses: BTreeMap<_, usize> = BTreeMap::new();
+        for (file, item) in analyzed
+            .results
+            .values()
+            .flat_map(|v| &v.0)
+            .flat_map(|(name, file)| file.items.iter().map(move |v| (name, v)))
+        {
+            let status = item.status();
+            if let FunctionStatus::Skipped(reason) = status {
+                log::warn!("{} in {file} was not analyzed: {reason}", item.name());
+            }
+            *statuses.entry(status.to_string()).or_default() += 1;
+        }
+        if !statuses.is_empty() {
+            let summary: Vec<_> = statuses
+                .iter()
+                .map(|(status, count)| format!("{count} {status}"))
+                .collect();
+            log::info!("functions: {}", summary.join(", "));
         }
+        for (file, func) in
+            analyzed
+                .view()
+                .into_iter()
+                .flat_map(|v| &v.0)
+                .flat_map(|(name, file)| {
+                    file.items
+                        .iter()
+                        .filter_map(Item::function)
+                        .map(move |v| (name, v))
+                })
+        {
+            for held in await_points::held_across_await(func) {
+                if held.is_guard() {
+                    log::warn!(
+                        "`{}` of type `{}` in {file} is held across `.await`",
+                        held.name,
+                        held.ty
+                    );
+                }
+            }
+        }
+        Ok(!analyzed.is_empty())
     }
 
     pub async fn shutdown_subprocesses(&self) {
@@ -288,6 +765,14 @@ impl Backend {
     }
 }
 
+/// Functions skipped because of the analysis budget
+fn over_budget_items(ws: &Workspace) -> impl Iterator<Item = &Item> {
+    ws.0.values()
+        .flat_map(|krate| krate.0.values())
+        .flat_map(|file| &file.items)
+        .filter(|item| matches!(item, Item::Skipped { reason, .. } if reason.is_over_budget()))
+}
+
 #[tower_lsp::async_trait]
 impl LanguageServer for Backend {
     async fn initialize(
@@ -303,8 +788,16 @@ impl LanguageServer for Backend {
         if let Some(wss) = params.workspace_folders {
             workspaces.extend(wss.iter().filter_map(|v| v.uri.to_file_path().ok()));
         }
+        if let Some(options) = params.initialization_options
+            && let Ok(options) = serde_json::from_value::<InitializationOptions>(options)
+        {
+            self.scheduler.set_limit(options.max_concurrent_jobs);
+            *self.options.write().await = options;
+        }
         for path in workspaces {
-            self.add_analyze_target(&path).await;
+            if let Err(e) = self.add_analyze_target(&path).await {
+                self.report_analyzer_error(e).await;
+            }
         }
         self.do_analyze().await;
 
@@ -324,6 +817,7 @@ impl LanguageServer for Backend {
         let server_cap = lsp_types::ServerCapabilities {
             text_document_sync: Some(lsp_types::TextDocumentSyncCapability::Options(sync_options)),
             workspace: Some(workspace_cap),
+            document_symbol_provider: Some(lsp_types::OneOf::Left(true)),
             ..Default::default()
         };
         let init_res = lsp_types::InitializeResult {
@@ -357,10 +851,11 @@ impl LanguageServer for Backend {
         params: lsp_types::DidChangeWorkspaceFoldersParams,
     ) -> () {
         for added in params.event.added {
-            if let Ok(path) = added.uri.to_file_path()
-                && self.add_analyze_target(&path).await
-            {
-                self.do_analyze().await;
+            if let Ok(path) = added.uri.to_file_path() {
+                match self.add_analyze_target(&path).await {
+                    Ok(()) => self.do_analyze().await,
+                    Err(e) => self.report_analyzer_error(e).await,
+                }
             }
         }
     }
@@ -369,14 +864,41 @@ impl LanguageServer for Backend {
         if let Ok(path) = params.text_document.uri.to_file_path()
             && path.is_file()
             && params.text_document.language_id == "rust"
-            && self.add_analyze_target(&path).await
         {
-            self.do_analyze().await;
+            *self.focused.write().await = Some(path.clone());
+            // dependencies are analyzed on demand by the workspace which depends on them
+            let is_dependency = self
+                .analyzers
+                .read()
+                .await
+                .iter()
+                .any(|v| v.dependency_package(&path).is_some());
+            if is_dependency {
+                self.do_analyze().await;
+                return;
+            }
+            match self.add_analyze_target(&path).await {
+                Ok(()) => self.do_analyze().await,
+                Err(e) => self.report_analyzer_error(e).await,
+            }
+        }
+    }
+
+    async fn document_symbol(
+        &self,
+        params: lsp_types::DocumentSymbolParams,
+    ) -> jsonrpc::Result<Option<lsp_types::DocumentSymbolResponse>> {
+        if let Ok(path) = params.text_document.uri.to_file_path()
+            && let Ok(text) = std::fs::read_to_string(&path)
+        {
+            let symbols = self.symbols(&path, &text).await;
+            return Ok(Some(lsp_types::DocumentSymbolResponse::Nested(symbols)));
         }
+        Ok(None)
     }
 
     async fn did_change(&self, _params: lsp_types::DidChangeTextDocumentParams) {
-        *self.analyzed.write().await = None;
+        self.analyzed.write().await.clear();
         self.shutdown_subprocesses().await;
     }
 
@@ -0,0 +1,228 @@
+//! Locals which a borrow flows into, following reborrows and moves of the reference
+
+use crate::models::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Whether a value of the type may carry a borrow
+fn may_hold_borrow(ty: &MirType) -> bool {
+    ty.reference.is_some() || ty.name.contains('\'')
+}
+
+/// Locals which the value of the local flows into, in the order of the body
+fn successors(func: &Function) -> HashMap<FnLocal, Vec<FnLocal>> {
+    let whole = |operand: &MirOperand| match operand {
+        MirOperand::Copy { place } | MirOperand::Move { place } if place.projection.is_empty() => {
+            Some(place.local)
+        }
+        _ => None,
+    };
+    let holds_borrow: HashSet<_> = func
+        .decls
+        .iter()
+        .filter_map(|decl| match decl {
+            MirDecl::User { local, ty, .. } | MirDecl::Other { local, ty, .. } => {
+                may_hold_borrow(ty).then_some(*local)
+            }
+        })
+        .collect();
+
+    let mut edges: HashMap<FnLocal, Vec<FnLocal>> = HashMap::new();
+    let mut add = |from: FnLocal, to: &MirPlace| {
+        if to.projection.is_empty() && from != to.local {
+            edges.entry(from).or_default().push(to.local);
+        }
+    };
+    for loan in &func.loans {
+        // a reborrow through the reference, e.g. `&mut *r`
+        if loan
+            .borrowed
+            .projection
+            .iter()
+            .any(|v| matches!(v, MirProjectionElem::Deref))
+        {
+            let holder = MirPlace {
+                local: loan.holder,
+                projection: Vec::new(),
+            };
+            add(loan.borrowed.local, &holder);
+        }
+    }
+    for bb in &func.basic_blocks {
+        for stmt in &bb.statements {
+            if let MirStatementKind::Assign { place, rval } = &stmt.kind {
+                match rval {
+                    MirRval::Use { operand } | MirRval::Cast { operand } => {
+                        if let Some(local) = whole(operand) {
+                            add(local, place);
+                        }
+                    }
+                    MirRval::Aggregate { fields } => {
+                        for local in fields.iter().filter_map(whole) {
+                            add(local, place);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        // the returned value may be derived from the borrow passed in
+        if let MirTerminatorKind::Call {
+            args, destination, ..
+        } = &bb.terminator.kind
+            && holds_borrow.contains(&destination.local)
+        {
+            for local in args.iter().filter_map(whole) {
+                add(local, destination);
+            }
+        }
+    }
+    edges
+}
+
+/// Flow of values between the locals of a function, built once and queried for each borrow
+pub struct BorrowGraph {
+    edges: HashMap<FnLocal, Vec<FnLocal>>,
+    users: HashSet<FnLocal>,
+}
+impl BorrowGraph {
+    pub fn new(func: &Function) -> Self {
+        let users = func
+            .decls
+            .iter()
+            .filter_map(|decl| match decl {
+                MirDecl::User { local, .. } => Some(*local),
+                MirDecl::Other { .. } => None,
+            })
+            .collect();
+        Self {
+            edges: successors(func),
+            users,
+        }
+    }
+
+    /// User variables which hold the borrow, from the one it is assigned to first
+    pub fn holders(&self, loan: &MirLoan) -> Vec<FnLocal> {
+        let mut result = Vec::new();
+        let mut visited = HashSet::from([loan.borrowed.local, loan.holder]);
+        let mut queue = VecDeque::from([loan.holder]);
+        while let Some(local) = queue.pop_front() {
+            if self.users.contains(&local) {
+                result.push(local);
+            }
+            for next in self.edges.get(&local).into_iter().flatten() {
+                if visited.insert(*next) {
+                    queue.push_back(*next);
+                }
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn place(id: u32, projection: Vec<MirProjectionElem>) -> MirPlace {
+        MirPlace {
+            local: FnLocal::new(id, 0),
+            projection,
+        }
+    }
+
+    fn decl(id: u32, name: &str, ty: &str) -> MirDecl {
+        MirDecl::User {
+            local: FnLocal::new(id, 0),
+            name: name.to_owned(),
+            span: Range::new(Loc(0), Loc(1)).unwrap(),
+            ty: MirType {
+                name: ty.to_owned(),
+                reference: None,
+            },
+            lives: Vec::new(),
+            field_borrows: Vec::new(),
+            drop: false,
+            drop_range: Vec::new(),
+            definitely_live_at: Vec::new(),
+            maybe_init_at: Vec::new(),
+            must_live_at: Vec::new(),
+            storage_range: Vec::new(),
+        }
+    }
+
+    fn assign(to: u32, rval: MirRval) -> MirStatement {
+        MirStatement {
+            kind: MirStatementKind::Assign {
+                place: place(to, Vec::new()),
+                rval,
+            },
+            range: None,
+        }
+    }
+
+    fn loan(id: u32, borrowed: MirPlace, holder: u32) -> MirLoan {
+        MirLoan {
+            id,
+            borrowed,
+            holder: FnLocal::new(holder, 0),
+            mutable: true,
+            range: None,
+            chain: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn follows_reborrows_and_moves() {
+        // let r = &mut v; let r2 = &mut *r; let r3 = r2; let w = Wrap(r);
+        let func = Function {
+            fn_id: 0,
+            name: "f".to_owned(),
+            basic_blocks: vec![MirBasicBlock {
+                statements: vec![
+                    assign(
+                        3,
+                        MirRval::Use {
+                            operand: MirOperand::Move {
+                                place: place(2, Vec::new()),
+                            },
+                        },
+                    ),
+                    assign(
+                        4,
+                        MirRval::Aggregate {
+                            fields: vec![MirOperand::Copy {
+                                place: place(1, Vec::new()),
+                            }],
+                        },
+                    ),
+                ],
+                terminator: MirTerminator {
+                    kind: MirTerminatorKind::Return,
+                    range: None,
+                },
+            }],
+            decls: vec![
+                decl(0, "v", "Vec<i32>"),
+                decl(1, "r", "&mut Vec<i32>"),
+                decl(2, "r2", "&mut Vec<i32>"),
+                decl(3, "r3", "&mut Vec<i32>"),
+                decl(4, "w", "Wrap<'_>"),
+            ],
+            closure: None,
+            fields: Vec::new(),
+            loans: vec![
+                loan(0, place(0, Vec::new()), 1),
+                loan(1, place(1, vec![MirProjectionElem::Deref]), 2),
+            ],
+            conflicts: Vec::new(),
+            lifetimes: Vec::new(),
+        };
+        let graph = BorrowGraph::new(&func);
+        let ids: Vec<_> = graph
+            .holders(&func.loans[0])
+            .into_iter()
+            .map(|v| v.id)
+            .collect();
+        assert_eq!(ids, vec![1, 2, 4, 3]);
+    }
+}
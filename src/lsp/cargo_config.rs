@@ -0,0 +1,150 @@
+//! Settings read from Cargo configuration files (`.cargo/config.toml`)
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Configuration files which cargo reads for the directory, nearest first
+fn config_files(dir: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<_> = dir.ancestors().map(|v| v.join(".cargo")).collect();
+    if let Some(cargo_home) = env::var_os("CARGO_HOME") {
+        dirs.push(PathBuf::from(cargo_home));
+    }
+    dirs.into_iter()
+        .flat_map(|dir| [dir.join("config.toml"), dir.join("config")])
+        .filter(|v| v.is_file())
+        .collect()
+}
+
+#[derive(serde::Deserialize, Default, Debug)]
+#[serde(default)]
+struct CargoConfig {
+    build: BuildConfig,
+}
+
+#[derive(serde::Deserialize, Default, Debug)]
+#[serde(default)]
+struct BuildConfig {
+    target: Option<BuildTarget>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(untagged)]
+enum BuildTarget {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// A configuration file which cannot be parsed, which cargo also refuses to read
+#[derive(Debug)]
+pub struct CargoConfigError {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+/// Target triples of `build.target`, which `cargo check` builds for by default
+///
+/// An empty list means the host.
+pub fn build_targets(dir: &Path) -> Result<Vec<String>, CargoConfigError> {
+    if let Ok(target) = env::var("CARGO_BUILD_TARGET") {
+        return Ok(vec![target]);
+    }
+    for path in config_files(dir) {
+        let Ok(config) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        // relative paths are relative to the parent of the `.cargo` directory
+        let base = path.parent().and_then(|v| v.parent()).unwrap_or(dir);
+        let targets = parse_build_targets(&config, base).map_err(|message| CargoConfigError {
+            path: path.clone(),
+            message,
+        })?;
+        if let Some(targets) = targets {
+            return Ok(targets);
+        }
+    }
+    Ok(Vec::new())
+}
+
+/// Read `target` of the `[build]` table, either a string or an array of strings
+///
+/// Each target is a triple or the path of a target specification (`.json`).
+fn parse_build_targets(config: &str, base: &Path) -> Result<Option<Vec<String>>, String> {
+    let config: CargoConfig = toml::from_str(config).map_err(|e| e.message().to_owned())?;
+    let targets = match config.build.target {
+        Some(BuildTarget::One(target)) => vec![target],
+        Some(BuildTarget::Many(targets)) => targets,
+        None => return Ok(None),
+    };
+    let targets = targets
+        .into_iter()
+        .map(|target| {
+            if target.ends_with(".json") {
+                base.join(target).to_string_lossy().into_owned()
+            } else {
+                target
+            }
+        })
+        .collect();
+    Ok(Some(targets))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(config: &str) -> Result<Option<Vec<String>>, String> {
+        parse_build_targets(config, Path::new("/project"))
+    }
+
+    #[test]
+    fn parse_build_target() {
+        assert_eq!(
+            parse("[build]\ntarget = \"wasm32-unknown-unknown\"\n"),
+            Ok(Some(vec!["wasm32-unknown-unknown".to_owned()]))
+        );
+        assert_eq!(
+            parse("build.target = [\"thumbv7em-none-eabihf\", \"x86_64-unknown-linux-gnu\"]\n"),
+            Ok(Some(vec![
+                "thumbv7em-none-eabihf".to_owned(),
+                "x86_64-unknown-linux-gnu".to_owned()
+            ]))
+        );
+        assert_eq!(
+            parse("[target.x86_64-unknown-linux-gnu]\ntarget = \"no\"\n"),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn parse_multi_line_array() {
+        assert_eq!(
+            parse("[build]\ntarget = [\n  \"a\",\n  \"b\", # comment\n]\n"),
+            Ok(Some(vec!["a".to_owned(), "b".to_owned()]))
+        );
+    }
+
+    #[test]
+    fn parse_hash_in_string() {
+        assert_eq!(
+            parse("[build]\ntarget = \"custom#1.json\" # comment\n"),
+            Ok(Some(vec!["/project/custom#1.json".to_owned()]))
+        );
+    }
+
+    #[test]
+    fn resolve_target_spec() {
+        assert_eq!(
+            parse("[build]\ntarget = [\"specs/board.json\", \"/abs/board.json\"]\n"),
+            Ok(Some(vec![
+                "/project/specs/board.json".to_owned(),
+                "/abs/board.json".to_owned()
+            ]))
+        );
+    }
+
+    #[test]
+    fn report_invalid_config() {
+        assert!(parse("[build]\ntarget = [\"a\"\n").is_err());
+        assert!(parse("[build]\ntarget = 1\n").is_err());
+    }
+}
@@ -1,5 +1,9 @@
-use crate::{lsp::progress, models::*, utils};
-use std::collections::HashSet;
+use crate::{
+    lsp::{await_points, borrow_graph, progress},
+    models::*,
+    utils,
+};
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use tower_lsp::lsp_types;
 
@@ -19,6 +23,12 @@ pub enum Deco<R = Range> {
         hover_text: String,
         overlapped: bool,
     },
+    Read {
+        local: FnLocal,
+        range: R,
+        hover_text: String,
+        overlapped: bool,
+    },
     ImmBorrow {
         local: FnLocal,
         range: R,
@@ -31,6 +41,12 @@ pub enum Deco<R = Range> {
         hover_text: String,
         overlapped: bool,
     },
+    RawPtr {
+        local: FnLocal,
+        range: R,
+        hover_text: String,
+        overlapped: bool,
+    },
     Move {
         local: FnLocal,
         range: R,
@@ -43,6 +59,12 @@ pub enum Deco<R = Range> {
         hover_text: String,
         overlapped: bool,
     },
+    HeldAcrossAwait {
+        local: FnLocal,
+        range: R,
+        hover_text: String,
+        overlapped: bool,
+    },
     SharedMut {
         local: FnLocal,
         range: R,
@@ -55,6 +77,12 @@ pub enum Deco<R = Range> {
         hover_text: String,
         overlapped: bool,
     },
+    Conflict {
+        local: FnLocal,
+        range: R,
+        hover_text: String,
+        overlapped: bool,
+    },
 
     DefinitelyLive {
         local: FnLocal,
@@ -68,6 +96,54 @@ pub enum Deco<R = Range> {
         hover_text: String,
         overlapped: bool,
     },
+    /// Where a local constrained by the selected lifetime parameter holds the borrow
+    LifetimeParam {
+        local: FnLocal,
+        range: R,
+        hover_text: String,
+        overlapped: bool,
+    },
+}
+impl<R> Deco<R> {
+    /// Type of the decoration, as serialized in the `type` field
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Deco::Lifetime { .. } => "lifetime",
+            Deco::Read { .. } => "read",
+            Deco::ImmBorrow { .. } => "imm_borrow",
+            Deco::MutBorrow { .. } => "mut_borrow",
+            Deco::RawPtr { .. } => "raw_ptr",
+            Deco::Move { .. } => "move",
+            Deco::Call { .. } => "call",
+            Deco::HeldAcrossAwait { .. } => "held_across_await",
+            Deco::SharedMut { .. } => "shared_mut",
+            Deco::Outlive { .. } => "outlive",
+            Deco::Conflict { .. } => "conflict",
+            Deco::DefinitelyLive { .. } => "definitely_live",
+            Deco::MaybeInitialized { .. } => "maybe_initialized",
+            Deco::LifetimeParam { .. } => "lifetime_param",
+        }
+    }
+}
+impl<R: Copy> Deco<R> {
+    pub fn range(&self) -> R {
+        match self {
+            Deco::Lifetime { range, .. }
+            | Deco::Read { range, .. }
+            | Deco::ImmBorrow { range, .. }
+            | Deco::MutBorrow { range, .. }
+            | Deco::RawPtr { range, .. }
+            | Deco::Move { range, .. }
+            | Deco::Call { range, .. }
+            | Deco::HeldAcrossAwait { range, .. }
+            | Deco::SharedMut { range, .. }
+            | Deco::Outlive { range, .. }
+            | Deco::Conflict { range, .. }
+            | Deco::DefinitelyLive { range, .. }
+            | Deco::MaybeInitialized { range, .. }
+            | Deco::LifetimeParam { range, .. } => *range,
+        }
+    }
 }
 impl Deco<Range> {
     pub fn to_lsp_range(&self, s: &str) -> Deco<lsp_types::Range> {
@@ -95,6 +171,29 @@ impl Deco<Range> {
                     overlapped,
                 }
             }
+            Deco::Read {
+                local,
+                range,
+                hover_text,
+                overlapped,
+            } => {
+                let start = utils::index_to_line_char(s, range.from());
+                let end = utils::index_to_line_char(s, range.until());
+                let start = lsp_types::Position {
+                    line: start.0,
+                    character: start.1,
+                };
+                let end = lsp_types::Position {
+                    line: end.0,
+                    character: end.1,
+                };
+                Deco::Read {
+                    local,
+                    range: lsp_types::Range { start, end },
+                    hover_text,
+                    overlapped,
+                }
+            }
             Deco::ImmBorrow {
                 local,
                 range,
@@ -141,6 +240,29 @@ impl Deco<Range> {
                     overlapped,
                 }
             }
+            Deco::RawPtr {
+                local,
+                range,
+                hover_text,
+                overlapped,
+            } => {
+                let start = utils::index_to_line_char(s, range.from());
+                let end = utils::index_to_line_char(s, range.until());
+                let start = lsp_types::Position {
+                    line: start.0,
+                    character: start.1,
+                };
+                let end = lsp_types::Position {
+                    line: end.0,
+                    character: end.1,
+                };
+                Deco::RawPtr {
+                    local,
+                    range: lsp_types::Range { start, end },
+                    hover_text,
+                    overlapped,
+                }
+            }
             Deco::Move {
                 local,
                 range,
@@ -187,6 +309,29 @@ impl Deco<Range> {
                     overlapped,
                 }
             }
+            Deco::HeldAcrossAwait {
+                local,
+                range,
+                hover_text,
+                overlapped,
+            } => {
+                let start = utils::index_to_line_char(s, range.from());
+                let end = utils::index_to_line_char(s, range.until());
+                let start = lsp_types::Position {
+                    line: start.0,
+                    character: start.1,
+                };
+                let end = lsp_types::Position {
+                    line: end.0,
+                    character: end.1,
+                };
+                Deco::HeldAcrossAwait {
+                    local,
+                    range: lsp_types::Range { start, end },
+                    hover_text,
+                    overlapped,
+                }
+            }
             Deco::SharedMut {
                 local,
                 range,
@@ -234,6 +379,29 @@ impl Deco<Range> {
                     overlapped,
                 }
             }
+            Deco::Conflict {
+                local,
+                range,
+                hover_text,
+                overlapped,
+            } => {
+                let start = utils::index_to_line_char(s, range.from());
+                let end = utils::index_to_line_char(s, range.until());
+                let start = lsp_types::Position {
+                    line: start.0,
+                    character: start.1,
+                };
+                let end = lsp_types::Position {
+                    line: end.0,
+                    character: end.1,
+                };
+                Deco::Conflict {
+                    local,
+                    range: lsp_types::Range { start, end },
+                    hover_text,
+                    overlapped,
+                }
+            }
 
             Deco::DefinitelyLive {
                 local,
@@ -281,6 +449,29 @@ impl Deco<Range> {
                     overlapped,
                 }
             }
+            Deco::LifetimeParam {
+                local,
+                range,
+                hover_text,
+                overlapped,
+            } => {
+                let start = utils::index_to_line_char(s, range.from());
+                let end = utils::index_to_line_char(s, range.until());
+                let start = lsp_types::Position {
+                    line: start.0,
+                    character: start.1,
+                };
+                let end = lsp_types::Position {
+                    line: end.0,
+                    character: end.1,
+                };
+                Deco::LifetimeParam {
+                    local,
+                    range: lsp_types::Range { start, end },
+                    hover_text,
+                    overlapped,
+                }
+            }
         }
     }
 }
@@ -313,6 +504,7 @@ enum SelectReason {
     Move,
     Drop,
     Borrow,
+    Read,
     Call,
 }
 #[derive(Clone, Debug)]
@@ -320,6 +512,8 @@ pub struct SelectLocal {
     pos: Loc,
     candidate_local_decls: Vec<FnLocal>,
     selected: Option<(SelectReason, FnLocal, Range)>,
+    /// Lifetime parameter in a signature, by the `fn_id` and the name
+    lifetime: Option<(u32, String)>,
 }
 impl SelectLocal {
     pub fn new(pos: Loc) -> Self {
@@ -327,6 +521,7 @@ impl SelectLocal {
             pos,
             candidate_local_decls: Vec::new(),
             selected: None,
+            lifetime: None,
         }
     }
 
@@ -342,7 +537,7 @@ impl SelectLocal {
                             self.selected = Some((reason, local, range));
                         }
                     (SelectReason::Var, _) => {}
-                    (_, SelectReason::Move) | (_, SelectReason::Borrow)
+                    (_, SelectReason::Move) | (_, SelectReason::Borrow) | (_, SelectReason::Read)
                         if range.size() < old_range.size() => {
                             self.selected = Some((reason, local, range));
                         }
@@ -363,6 +558,12 @@ impl SelectLocal {
         self.selected.map(|v| v.1)
     }
 
+    pub fn selected_lifetime(&self) -> Option<(u32, &str)> {
+        self.lifetime
+            .as_ref()
+            .map(|(fn_id, name)| (*fn_id, name.as_str()))
+    }
+
     pub fn select_operand(&mut self, operand: &MirOperand, range: Range) {
         if let MirOperand::Move { place } = operand {
             self.select(SelectReason::Move, place.local, range);
@@ -370,6 +571,28 @@ impl SelectLocal {
     }
 }
 impl utils::MirVisitor for SelectLocal {
+    fn visit_func(&mut self, func: &Function) {
+        for lifetime in &func.lifetimes {
+            if lifetime
+                .spans
+                .iter()
+                .any(|v| v.from() <= self.pos && self.pos <= v.until())
+            {
+                self.lifetime = Some((func.fn_id, lifetime.name.clone()));
+            }
+        }
+        // captured variables are selected from the closure body
+        for capture in func.closure.iter().flat_map(|v| &v.captures) {
+            if !self.candidate_local_decls.contains(&capture.local) {
+                self.candidate_local_decls.push(capture.local);
+            }
+            let reason = match capture.kind {
+                MirCaptureKind::ByRef | MirCaptureKind::ByMutRef => SelectReason::Borrow,
+                MirCaptureKind::ByMove => SelectReason::Move,
+            };
+            self.select(reason, capture.local, capture.range);
+        }
+    }
     fn visit_decl(&mut self, decl: &MirDecl) {
         let (local, ty) = match decl {
             MirDecl::User { local, ty, .. } => (local, ty),
@@ -384,30 +607,39 @@ impl utils::MirVisitor for SelectLocal {
         }
     }
     fn visit_stmt(&mut self, stmt: &MirStatement) {
-        if let Some(range) = stmt.range
-            && let MirStatementKind::Assign { rval, .. } = &stmt.kind
-        {
-            match rval {
+        let Some(range) = stmt.range else {
+            return;
+        };
+        match &stmt.kind {
+            MirStatementKind::Assign { rval, .. } => match rval {
                 MirRval::Use { operand }
                 | MirRval::Repeat { operand }
                 | MirRval::Cast { operand }
-                | MirRval::UnaryOp { operand } => {
+                | MirRval::UnaryOp { operand }
+                | MirRval::WrapUnsafeBinder { operand } => {
                     self.select_operand(operand, range);
                 }
                 MirRval::BinaryOp { left, right } => {
                     self.select_operand(left, range);
                     self.select_operand(right, range);
                 }
-                MirRval::Ref { place, .. } => {
+                MirRval::Ref { place, .. } | MirRval::RawPtr { place, .. } => {
                     self.select(SelectReason::Borrow, place.local, range);
                 }
+                MirRval::Discriminant { place } | MirRval::CopyForDeref { place } => {
+                    self.select(SelectReason::Read, place.local, range);
+                }
                 MirRval::Aggregate { fields } => {
                     for field in fields {
                         self.select_operand(field, range);
                     }
                 }
-                MirRval::Other => {}
+                MirRval::ThreadLocalRef | MirRval::PtrMetadata { .. } => {}
+            },
+            MirStatementKind::FakeRead { place, .. } | MirStatementKind::PlaceMention { place } => {
+                self.select(SelectReason::Read, place.local, range);
             }
+            _ => {}
         }
     }
     fn visit_term(&mut self, term: &MirTerminator) {
@@ -433,6 +665,9 @@ impl utils::MirVisitor for SelectLocal {
                 MirTerminatorKind::Assert { cond, .. } => {
                     self.select_operand(cond, range);
                 }
+                MirTerminatorKind::Yield { value, .. } => {
+                    self.select_operand(value, range);
+                }
                 MirTerminatorKind::Drop { place, .. } => {
                     self.select(SelectReason::Drop, place.local, range);
                 }
@@ -440,16 +675,44 @@ impl utils::MirVisitor for SelectLocal {
                 | MirTerminatorKind::SwitchInt { .. }
                 | MirTerminatorKind::Return
                 | MirTerminatorKind::Unreachable
-                | MirTerminatorKind::Other { .. } => {}
+                | MirTerminatorKind::CoroutineDrop
+                | MirTerminatorKind::UnwindResume
+                | MirTerminatorKind::UnwindTerminate
+                | MirTerminatorKind::FalseEdge { .. }
+                | MirTerminatorKind::FalseUnwind { .. }
+                | MirTerminatorKind::InlineAsm { .. } => {}
             }
         }
     }
 }
+/// Variable holding a borrow, with where it is live
+#[derive(Clone, Debug)]
+struct Holder {
+    name: String,
+    lives: Vec<Range>,
+}
 #[derive(Clone, Debug)]
 pub struct CalcDecos {
     locals: HashSet<FnLocal>,
     decorations: Vec<Deco>,
     current_fn_id: u32,
+    /// Captures by reference with the local holding the closure,
+    /// resolved after all functions are visited since the closure may precede its parent
+    captures: Vec<(FnLocal, MirCapture)>,
+    lives: HashMap<FnLocal, Vec<Range>>,
+    /// Names of the selected variables and their fields, to show the moved or borrowed place
+    names: HashMap<FnLocal, String>,
+    fields: HashMap<(FnLocal, Vec<usize>), Vec<String>>,
+    /// Moves out of a field, resolved after all functions are visited to tell which fields
+    /// remain usable
+    partial_moves: Vec<(MirPlace, Range)>,
+    /// Variables holding the borrows of the selected locals with where they are live,
+    /// by the borrowed local and where the borrow is created
+    holders: HashMap<(FnLocal, Range), Vec<Holder>>,
+    /// Cursor position, to tell which holders currently hold a borrow
+    cursor: Option<Loc>,
+    /// Selected lifetime parameter, by the `fn_id` and the name
+    lifetime: Option<(u32, String)>,
 }
 impl CalcDecos {
     pub fn new(locals: impl IntoIterator<Item = FnLocal>) -> Self {
@@ -457,6 +720,78 @@ impl CalcDecos {
             locals: locals.into_iter().collect(),
             decorations: Vec::new(),
             current_fn_id: 0,
+            captures: Vec::new(),
+            lives: HashMap::new(),
+            names: HashMap::new(),
+            fields: HashMap::new(),
+            partial_moves: Vec::new(),
+            holders: HashMap::new(),
+            cursor: None,
+            lifetime: None,
+        }
+    }
+
+    /// Name only the holders live at the position in the hover text of borrows
+    pub fn set_cursor(&mut self, pos: Loc) {
+        self.cursor = Some(pos);
+    }
+
+    /// Holders live at the cursor, or the whole chain if none of them is
+    fn holders_text(&self, holders: &[Holder]) -> String {
+        let current: Vec<_> = holders
+            .iter()
+            .filter(|holder| {
+                self.cursor.is_some_and(|pos| {
+                    holder
+                        .lives
+                        .iter()
+                        .any(|v| v.from() <= pos && pos <= v.until())
+                })
+            })
+            .map(|holder| format!("`{}`", holder.name))
+            .collect();
+        if !current.is_empty() {
+            return format!(" currently held by {}", current.join(", "));
+        }
+        let Some((first, rest)) = holders.split_first() else {
+            return String::new();
+        };
+        let mut text = format!(" held by `{}`", first.name);
+        if !rest.is_empty() {
+            let rest: Vec<_> = rest.iter().map(|v| format!("`{}`", v.name)).collect();
+            text.push_str(&format!(", then {}", rest.join(", ")));
+        }
+        text
+    }
+
+    /// Show where the locals constrained by the lifetime parameter are live, instead of locals
+    pub fn select_lifetime(&mut self, fn_id: u32, name: &str) {
+        self.lifetime = Some((fn_id, name.to_owned()));
+    }
+
+    fn calc_lifetime(&mut self, func: &Function, lifetime: &MirLifetime) {
+        for constrained in &lifetime.constrained {
+            let var_str = func
+                .decls
+                .iter()
+                .find_map(|decl| match decl {
+                    MirDecl::User { local, name, .. } if *local == constrained.local => {
+                        Some(format!("variable `{name}`"))
+                    }
+                    _ => None,
+                })
+                .unwrap_or_else(|| "anonymous variable".to_owned());
+            for range in &constrained.ranges {
+                self.decorations.push(Deco::LifetimeParam {
+                    local: constrained.local,
+                    range: *range,
+                    hover_text: format!(
+                        "{var_str} holds a borrow which must outlive `{}`",
+                        lifetime.name
+                    ),
+                    overlapped: false,
+                });
+            }
         }
     }
 
@@ -465,34 +800,34 @@ impl CalcDecos {
             Deco::Lifetime { .. } => 0,
             Deco::MaybeInitialized { .. } => 1,
             Deco::DefinitelyLive { .. } => 2,
-            Deco::ImmBorrow { .. } => 3,
-            Deco::MutBorrow { .. } => 4,
-            Deco::Move { .. } => 5,
-            Deco::Call { .. } => 6,
-            Deco::SharedMut { .. } => 7,
-            Deco::Outlive { .. } => 8,
+            Deco::LifetimeParam { .. } => 3,
+            Deco::Read { .. } => 4,
+            Deco::ImmBorrow { .. } => 5,
+            Deco::MutBorrow { .. } => 6,
+            Deco::RawPtr { .. } => 7,
+            Deco::Move { .. } => 8,
+            Deco::Call { .. } => 9,
+            Deco::HeldAcrossAwait { .. } => 10,
+            Deco::SharedMut { .. } => 11,
+            Deco::Conflict { .. } => 12,
+            Deco::Outlive { .. } => 13,
         }
     }
 
+    /// Sort by the kind, then by the position so that the result does not depend on the order
+    /// the functions are visited
     fn sort_by_definition(&mut self) {
-        self.decorations.sort_by_key(Self::get_deco_order);
+        self.decorations
+            .sort_by_key(|v| (Self::get_deco_order(v), v.range().from()));
     }
 
     pub fn handle_overlapping(&mut self) {
+        self.calc_captures();
+        self.calc_partial_moves();
         self.sort_by_definition();
         let mut i = 1;
         'outer: while i < self.decorations.len() {
-            let current_range = match &self.decorations[i] {
-                Deco::Lifetime { range, .. }
-                | Deco::ImmBorrow { range, .. }
-                | Deco::MutBorrow { range, .. }
-                | Deco::Move { range, .. }
-                | Deco::Call { range, .. }
-                | Deco::SharedMut { range, .. }
-                | Deco::Outlive { range, .. }
-                | Deco::DefinitelyLive { range, .. }
-                | Deco::MaybeInitialized { range, .. } => *range,
-            };
+            let current_range = self.decorations[i].range();
 
             let mut j = 0;
             while j < i {
@@ -505,29 +840,44 @@ impl CalcDecos {
                     Deco::Lifetime {
                         range, overlapped, ..
                     }
+                    | Deco::Read {
+                        range, overlapped, ..
+                    }
                     | Deco::ImmBorrow {
                         range, overlapped, ..
                     }
                     | Deco::MutBorrow {
                         range, overlapped, ..
                     }
+                    | Deco::RawPtr {
+                        range, overlapped, ..
+                    }
                     | Deco::Move {
                         range, overlapped, ..
                     }
                     | Deco::Call {
                         range, overlapped, ..
                     }
+                    | Deco::HeldAcrossAwait {
+                        range, overlapped, ..
+                    }
                     | Deco::SharedMut {
                         range, overlapped, ..
                     }
                     | Deco::Outlive {
                         range, overlapped, ..
                     }
+                    | Deco::Conflict {
+                        range, overlapped, ..
+                    }
                     | Deco::DefinitelyLive {
                         range, overlapped, ..
                     }
                     | Deco::MaybeInitialized {
                         range, overlapped, ..
+                    }
+                    | Deco::LifetimeParam {
+                        range, overlapped, ..
                     } => (*range, *overlapped),
                 };
 
@@ -550,6 +900,14 @@ impl CalcDecos {
                                 hover_text: hover_text.clone(),
                                 overlapped: false,
                             },
+                            Deco::Read {
+                                local, hover_text, ..
+                            } => Deco::Read {
+                                local: *local,
+                                range,
+                                hover_text: hover_text.clone(),
+                                overlapped: false,
+                            },
                             Deco::ImmBorrow {
                                 local, hover_text, ..
                             } => Deco::ImmBorrow {
@@ -566,6 +924,14 @@ impl CalcDecos {
                                 hover_text: hover_text.clone(),
                                 overlapped: false,
                             },
+                            Deco::RawPtr {
+                                local, hover_text, ..
+                            } => Deco::RawPtr {
+                                local: *local,
+                                range,
+                                hover_text: hover_text.clone(),
+                                overlapped: false,
+                            },
                             Deco::Move {
                                 local, hover_text, ..
                             } => Deco::Move {
@@ -582,6 +948,14 @@ impl CalcDecos {
                                 hover_text: hover_text.clone(),
                                 overlapped: false,
                             },
+                            Deco::HeldAcrossAwait {
+                                local, hover_text, ..
+                            } => Deco::HeldAcrossAwait {
+                                local: *local,
+                                range,
+                                hover_text: hover_text.clone(),
+                                overlapped: false,
+                            },
                             Deco::SharedMut {
                                 local, hover_text, ..
                             } => Deco::SharedMut {
@@ -598,6 +972,14 @@ impl CalcDecos {
                                 hover_text: hover_text.clone(),
                                 overlapped: false,
                             },
+                            Deco::Conflict {
+                                local, hover_text, ..
+                            } => Deco::Conflict {
+                                local: *local,
+                                range,
+                                hover_text: hover_text.clone(),
+                                overlapped: false,
+                            },
                             Deco::DefinitelyLive {
                                 local, hover_text, ..
                             } => Deco::DefinitelyLive {
@@ -614,6 +996,14 @@ impl CalcDecos {
                                 hover_text: hover_text.clone(),
                                 overlapped: false,
                             },
+                            Deco::LifetimeParam {
+                                local, hover_text, ..
+                            } => Deco::LifetimeParam {
+                                local: *local,
+                                range,
+                                hover_text: hover_text.clone(),
+                                overlapped: false,
+                            },
                         };
                         new_decos.push(new_deco);
                     }
@@ -622,29 +1012,44 @@ impl CalcDecos {
                         Deco::Lifetime {
                             range, overlapped, ..
                         }
+                        | Deco::Read {
+                            range, overlapped, ..
+                        }
                         | Deco::ImmBorrow {
                             range, overlapped, ..
                         }
                         | Deco::MutBorrow {
                             range, overlapped, ..
                         }
+                        | Deco::RawPtr {
+                            range, overlapped, ..
+                        }
                         | Deco::Move {
                             range, overlapped, ..
                         }
                         | Deco::Call {
                             range, overlapped, ..
                         }
+                        | Deco::HeldAcrossAwait {
+                            range, overlapped, ..
+                        }
                         | Deco::SharedMut {
                             range, overlapped, ..
                         }
                         | Deco::Outlive {
                             range, overlapped, ..
                         }
+                        | Deco::Conflict {
+                            range, overlapped, ..
+                        }
                         | Deco::DefinitelyLive {
                             range, overlapped, ..
                         }
                         | Deco::MaybeInitialized {
                             range, overlapped, ..
+                        }
+                        | Deco::LifetimeParam {
+                            range, overlapped, ..
                         } => {
                             *range = common;
                             *overlapped = true;
@@ -661,6 +1066,186 @@ impl CalcDecos {
         }
     }
 
+    /// Borrows by closures, which last while the closure lives
+    fn calc_captures(&mut self) {
+        let mut decos = Vec::new();
+        for (holder, capture) in std::mem::take(&mut self.captures) {
+            let lives = self.lives.get(&holder).into_iter().flatten();
+            let from = lives.clone().map(|v| v.from()).min();
+            let until = lives.map(|v| v.until()).max();
+            let Some(range) = from
+                .zip(until)
+                .and_then(|(from, until)| Range::new(from, until))
+            else {
+                continue;
+            };
+            let deco = if capture.kind == MirCaptureKind::ByMutRef {
+                Deco::MutBorrow {
+                    local: capture.local,
+                    range,
+                    hover_text: format!("`{}` mutably borrowed by closure", capture.place),
+                    overlapped: false,
+                }
+            } else {
+                Deco::ImmBorrow {
+                    local: capture.local,
+                    range,
+                    hover_text: format!("`{}` borrowed by closure", capture.place),
+                    overlapped: false,
+                }
+            };
+            decos.push(deco);
+        }
+        // the captures themselves take precedence over the borrows
+        self.decorations.splice(0..0, decos);
+    }
+
+    /// Source-like name of the field of a variable, e.g. `s.name`
+    fn field_name(&self, local: FnLocal, fields: &[usize]) -> Option<String> {
+        let mut name = self.names.get(&local)?.clone();
+        for (i, index) in fields.iter().enumerate() {
+            match self
+                .fields
+                .get(&(local, fields[..i].to_vec()))
+                .and_then(|v| v.get(*index))
+            {
+                Some(field) => name.push_str(&format!(".{field}")),
+                None => name.push_str(&format!(".{index}")),
+            }
+        }
+        Some(name)
+    }
+
+    /// Moves out of fields, with the sibling fields which are not moved so far
+    fn calc_partial_moves(&mut self) {
+        let moves = std::mem::take(&mut self.partial_moves);
+        for (place, range) in &moves {
+            let mut parent = place.fields();
+            let moved = parent.pop();
+            let hover_text = match self.field_name(place.local, &place.fields()) {
+                Some(name) => {
+                    let siblings = self
+                        .fields
+                        .get(&(place.local, parent.clone()))
+                        .map_or(0, Vec::len);
+                    let moved_before = |index| {
+                        moves.iter().any(|(other, other_range)| {
+                            let fields = other.fields();
+                            other.local == place.local
+                                && other_range.from() <= range.from()
+                                && fields.starts_with(&parent)
+                                && fields.get(parent.len()) == Some(&index)
+                        })
+                    };
+                    let usable: Vec<_> = (0..siblings)
+                        .filter(|index| Some(*index) != moved && !moved_before(*index))
+                        .filter_map(|index| {
+                            let mut fields = parent.clone();
+                            fields.push(index);
+                            self.field_name(place.local, &fields)
+                        })
+                        .map(|v| format!("`{v}`"))
+                        .collect();
+                    match usable.as_slice() {
+                        [] => format!("`{name}` moved"),
+                        [field] => format!("`{name}` moved; {field} is still usable"),
+                        fields => format!("`{name}` moved; {} are still usable", fields.join(", ")),
+                    }
+                }
+                None => "field moved".to_owned(),
+            };
+            self.decorations.push(Deco::Move {
+                local: place.local,
+                range: *range,
+                hover_text,
+                overlapped: false,
+            });
+        }
+    }
+
+    /// The borrow, the access which conflicts with it and the use which keeps the borrow live,
+    /// labeled as the borrow checker does
+    fn calc_conflict(&mut self, loan: &MirLoan, conflict: &MirConflict) {
+        let local = loan.borrowed.local;
+        let name = self
+            .field_name(local, &loan.borrowed.fields())
+            .unwrap_or_else(|| "value".to_owned());
+        let (code, error, borrowed, access, used) = match (conflict.access, loan.mutable) {
+            (MirAccessKind::MutableBorrow, true) => (
+                "E0499",
+                format!("cannot borrow `{name}` as mutable more than once at a time"),
+                "first mutable borrow occurs here".to_owned(),
+                "second mutable borrow occurs here".to_owned(),
+                "first borrow later used here",
+            ),
+            (MirAccessKind::MutableBorrow, false) => (
+                "E0502",
+                format!(
+                    "cannot borrow `{name}` as mutable because it is also borrowed as immutable"
+                ),
+                "immutable borrow occurs here".to_owned(),
+                "mutable borrow occurs here".to_owned(),
+                "immutable borrow later used here",
+            ),
+            (MirAccessKind::SharedBorrow, _) => (
+                "E0502",
+                format!(
+                    "cannot borrow `{name}` as immutable because it is also borrowed as mutable"
+                ),
+                "mutable borrow occurs here".to_owned(),
+                "immutable borrow occurs here".to_owned(),
+                "mutable borrow later used here",
+            ),
+            (MirAccessKind::Move, _) => (
+                "E0505",
+                format!("cannot move out of `{name}` because it is borrowed"),
+                format!("borrow of `{name}` occurs here"),
+                format!("move out of `{name}` occurs here"),
+                "borrow later used here",
+            ),
+            (MirAccessKind::Assign, _) => (
+                "E0506",
+                format!("cannot assign to `{name}` because it is borrowed"),
+                format!("`{name}` is borrowed here"),
+                format!("`{name}` is assigned to here but it was already borrowed"),
+                "borrow later used here",
+            ),
+            (MirAccessKind::Read, _) => (
+                "E0503",
+                format!("cannot use `{name}` because it was mutably borrowed"),
+                format!("`{name}` is borrowed here"),
+                format!("use of borrowed `{name}`"),
+                "borrow later used here",
+            ),
+            // a borrowed value dropped while borrowed (E0597) is shown by `Deco::Outlive`
+            (MirAccessKind::Drop, _) => return,
+        };
+        // a use before the access keeps the borrow live into the next iteration of a loop
+        let iteration = if conflict
+            .used
+            .is_some_and(|used| used.from() < conflict.range.from())
+        {
+            ", in later iteration of loop"
+        } else {
+            ""
+        };
+        let ranges = [
+            (loan.range, format!("{borrowed} ({code})")),
+            (Some(conflict.range), format!("{access} ({code}: {error})")),
+            (conflict.used, format!("{used}{iteration} ({code})")),
+        ];
+        for (range, hover_text) in ranges {
+            if let Some(range) = range {
+                self.decorations.push(Deco::Conflict {
+                    local,
+                    range,
+                    hover_text,
+                    overlapped: false,
+                });
+            }
+        }
+    }
+
     pub fn decorations(self) -> Vec<Deco> {
         self.decorations
     }
@@ -669,6 +1254,10 @@ impl CalcDecos {
         if let MirOperand::Move { place } = operand
             && self.locals.contains(&place.local)
         {
+            if place.is_field() {
+                self.partial_moves.push((place.clone(), range));
+                return;
+            }
             self.decorations.push(Deco::Move {
                 local: place.local,
                 range,
@@ -677,6 +1266,16 @@ impl CalcDecos {
             });
         }
     }
+    fn visit_read(&mut self, place: &MirPlace, range: Range, hover_text: &str) {
+        if self.locals.contains(&place.local) {
+            self.decorations.push(Deco::Read {
+                local: place.local,
+                range,
+                hover_text: hover_text.to_string(),
+                overlapped: false,
+            });
+        }
+    }
     pub fn calc_call(&mut self, destination: &MirPlace, fn_span: Range) {
         for deco in &self.decorations {
             if let Deco::Call { range, .. } = deco
@@ -708,12 +1307,125 @@ impl CalcDecos {
     }
 }
 impl utils::MirVisitor for CalcDecos {
+    fn visit_func(&mut self, func: &Function) {
+        if let Some((fn_id, name)) = &self.lifetime
+            && *fn_id == func.fn_id
+            && let Some(lifetime) = func.lifetimes.iter().find(|v| v.name == *name)
+        {
+            self.calc_lifetime(func, lifetime);
+        }
+        for decl in &func.decls {
+            if let MirDecl::User { local, name, .. } = decl
+                && self.locals.contains(local)
+            {
+                self.names.insert(*local, name.clone());
+            }
+        }
+        for fields in &func.fields {
+            if self.locals.contains(&fields.local) {
+                self.fields
+                    .insert((fields.local, fields.path.clone()), fields.names.clone());
+            }
+        }
+        let mut graph = None;
+        for loan in &func.loans {
+            let Some(range) = loan.range else {
+                continue;
+            };
+            if !self.locals.contains(&loan.borrowed.local) {
+                continue;
+            }
+            let graph = graph.get_or_insert_with(|| borrow_graph::BorrowGraph::new(func));
+            let found = graph.holders(loan).into_iter().filter_map(|holder| {
+                func.decls.iter().find_map(|decl| match decl {
+                    MirDecl::User {
+                        local, name, lives, ..
+                    } if *local == holder => Some(Holder {
+                        name: name.clone(),
+                        lives: lives.clone(),
+                    }),
+                    _ => None,
+                })
+            });
+            let holders = self
+                .holders
+                .entry((loan.borrowed.local, range))
+                .or_default();
+            for holder in found {
+                if !holders.iter().any(|v| v.name == holder.name) {
+                    holders.push(holder);
+                }
+            }
+        }
+        for conflict in &func.conflicts {
+            let Some(loan) = func.loans.iter().find(|v| v.id == conflict.loan) else {
+                continue;
+            };
+            if self.locals.contains(&loan.borrowed.local) {
+                self.calc_conflict(loan, conflict);
+            }
+        }
+        if let Some(closure) = &func.closure {
+            for capture in &closure.captures {
+                if !self.locals.contains(&capture.local) {
+                    continue;
+                }
+                let place = &capture.place;
+                let deco = match capture.kind {
+                    MirCaptureKind::ByRef => Deco::ImmBorrow {
+                        local: capture.local,
+                        range: capture.range,
+                        hover_text: format!("`{place}` captured by reference in closure"),
+                        overlapped: false,
+                    },
+                    MirCaptureKind::ByMutRef => Deco::MutBorrow {
+                        local: capture.local,
+                        range: capture.range,
+                        hover_text: format!("`{place}` captured by mutable reference in closure"),
+                        overlapped: false,
+                    },
+                    MirCaptureKind::ByMove => Deco::Move {
+                        local: capture.local,
+                        range: capture.range,
+                        hover_text: format!("`{place}` moved into closure"),
+                        overlapped: false,
+                    },
+                };
+                self.decorations.push(deco);
+                if capture.kind != MirCaptureKind::ByMove
+                    && let Some(holder) = closure.holder
+                {
+                    self.captures.push((holder, capture.clone()));
+                }
+            }
+        }
+        for held in await_points::held_across_await(func) {
+            if !self.locals.contains(&held.local) {
+                continue;
+            }
+            let hover_text = if held.is_guard() {
+                format!(
+                    "`{}` is held across this `.await` and blocks others",
+                    held.name
+                )
+            } else {
+                format!("variable `{}` is held across this `.await`", held.name)
+            };
+            for range in held.awaits {
+                self.decorations.push(Deco::HeldAcrossAwait {
+                    local: held.local,
+                    range,
+                    hover_text: hover_text.clone(),
+                    overlapped: false,
+                });
+            }
+        }
+    }
     fn visit_decl(&mut self, decl: &MirDecl) {
         let (
             local,
             lives,
-            shared_borrow,
-            mutable_borrow,
+            field_borrows,
             drop_range,
             must_live_at,
             storage_range,
@@ -726,8 +1438,7 @@ impl utils::MirVisitor for CalcDecos {
                 local,
                 name,
                 lives,
-                shared_borrow,
-                mutable_borrow,
+                field_borrows,
                 drop_range,
                 must_live_at,
                 storage_range,
@@ -738,8 +1449,7 @@ impl utils::MirVisitor for CalcDecos {
             } => (
                 *local,
                 lives,
-                shared_borrow,
-                mutable_borrow,
+                field_borrows,
                 drop_range,
                 must_live_at,
                 storage_range,
@@ -751,8 +1461,7 @@ impl utils::MirVisitor for CalcDecos {
             MirDecl::Other {
                 local,
                 lives,
-                shared_borrow,
-                mutable_borrow,
+                field_borrows,
                 drop_range,
                 must_live_at,
                 storage_range,
@@ -763,8 +1472,7 @@ impl utils::MirVisitor for CalcDecos {
             } => (
                 *local,
                 lives,
-                shared_borrow,
-                mutable_borrow,
+                field_borrows,
                 drop_range,
                 must_live_at,
                 storage_range,
@@ -775,6 +1483,7 @@ impl utils::MirVisitor for CalcDecos {
             ),
         };
         self.current_fn_id = local.fn_id;
+        self.lives.insert(local, lives.clone());
         if self.locals.contains(&local) {
             let var_str = match name {
                 Some(mir_var_name) => {
@@ -798,10 +1507,22 @@ impl utils::MirVisitor for CalcDecos {
                     overlapped: false,
                 });
             }
-            let mut borrow_ranges = shared_borrow.clone();
-            borrow_ranges.extend_from_slice(mutable_borrow);
-            let shared_mut = utils::common_ranges(&borrow_ranges);
-            for range in shared_mut {
+            // borrows of disjoint fields do not conflict
+            let mut shared_mut = Vec::new();
+            for (i, borrow) in field_borrows.iter().enumerate() {
+                for other in &field_borrows[i + 1..] {
+                    if (borrow.mutable || other.mutable) && borrow.overlaps(other) {
+                        for (r1, r2) in borrow
+                            .ranges
+                            .iter()
+                            .flat_map(|r1| other.ranges.iter().map(move |r2| (*r1, *r2)))
+                        {
+                            shared_mut.extend(utils::common_range(r1, r2));
+                        }
+                    }
+                }
+            }
+            for range in utils::eliminated_ranges(shared_mut) {
                 self.decorations.push(Deco::SharedMut {
                     local,
                     range,
@@ -839,14 +1560,16 @@ impl utils::MirVisitor for CalcDecos {
     }
 
     fn visit_stmt(&mut self, stmt: &MirStatement) {
-        if let Some(range) = stmt.range
-            && let MirStatementKind::Assign { rval, .. } = &stmt.kind
-        {
-            match rval {
+        let Some(range) = stmt.range else {
+            return;
+        };
+        match &stmt.kind {
+            MirStatementKind::Assign { rval, .. } => match rval {
                 MirRval::Use { operand }
                 | MirRval::Repeat { operand }
                 | MirRval::Cast { operand }
-                | MirRval::UnaryOp { operand } => {
+                | MirRval::UnaryOp { operand }
+                | MirRval::WrapUnsafeBinder { operand } => {
                     self.visit_operand(operand, range);
                 }
                 MirRval::BinaryOp { left, right } => {
@@ -855,30 +1578,79 @@ impl utils::MirVisitor for CalcDecos {
                 }
                 MirRval::Ref { place, mutable } => {
                     if self.locals.contains(&place.local) {
+                        let borrow = if *mutable {
+                            "mutable borrow"
+                        } else {
+                            "immutable borrow"
+                        };
+                        let mut hover_text = match self.field_name(place.local, &place.fields()) {
+                            Some(name) if place.is_field() => format!("{borrow} of `{name}`"),
+                            _ => borrow.to_string(),
+                        };
+                        if let Some(holders) = self.holders.get(&(place.local, range)) {
+                            hover_text.push_str(&self.holders_text(holders));
+                        }
                         if *mutable {
                             self.decorations.push(Deco::MutBorrow {
                                 local: place.local,
                                 range,
-                                hover_text: "mutable borrow".to_string(),
+                                hover_text,
                                 overlapped: false,
                             });
                         } else {
                             self.decorations.push(Deco::ImmBorrow {
                                 local: place.local,
                                 range,
-                                hover_text: "immutable borrow".to_string(),
+                                hover_text,
                                 overlapped: false,
                             });
                         }
                     }
                 }
+                MirRval::RawPtr { place, mutable } => {
+                    if self.locals.contains(&place.local) {
+                        let hover_text = if *mutable {
+                            "mutable raw pointer taken"
+                        } else {
+                            "raw pointer taken"
+                        };
+                        self.decorations.push(Deco::RawPtr {
+                            local: place.local,
+                            range,
+                            hover_text: hover_text.to_string(),
+                            overlapped: false,
+                        });
+                    }
+                }
+                MirRval::Discriminant { place } => {
+                    self.visit_read(place, range, "discriminant read");
+                }
+                MirRval::CopyForDeref { place } => {
+                    self.visit_read(place, range, "copied for dereference");
+                }
                 MirRval::Aggregate { fields } => {
                     for field in fields {
                         self.visit_operand(field, range);
                     }
                 }
-                MirRval::Other => {}
+                MirRval::ThreadLocalRef | MirRval::PtrMetadata { .. } => {}
+            },
+            MirStatementKind::FakeRead { place, cause } => {
+                let hover_text = match cause {
+                    MirFakeReadCause::MatchedPlace => "read by `match`, not moved",
+                    MirFakeReadCause::MatchGuard | MirFakeReadCause::GuardBinding => {
+                        "read by match guard, not moved"
+                    }
+                    MirFakeReadCause::Index => "read by index expression, not moved",
+                    // the binding itself, already shown by its lifetime
+                    MirFakeReadCause::Let => return,
+                };
+                self.visit_read(place, range, hover_text);
+            }
+            MirStatementKind::PlaceMention { place } => {
+                self.visit_read(place, range, "mentioned, not read or moved");
             }
+            _ => {}
         }
     }
 
@@ -910,15 +1682,340 @@ impl utils::MirVisitor for CalcDecos {
                 MirTerminatorKind::Assert { cond, .. } => {
                     self.visit_operand(cond, range);
                 }
+                MirTerminatorKind::Yield { value, .. } => {
+                    self.visit_operand(value, range);
+                }
                 MirTerminatorKind::Goto { .. }
                 | MirTerminatorKind::SwitchInt { .. }
                 | MirTerminatorKind::Return
                 | MirTerminatorKind::Unreachable
                 | MirTerminatorKind::Drop { .. }
-                | MirTerminatorKind::Other { .. } => {}
+                | MirTerminatorKind::CoroutineDrop
+                | MirTerminatorKind::UnwindResume
+                | MirTerminatorKind::UnwindTerminate
+                | MirTerminatorKind::FalseEdge { .. }
+                | MirTerminatorKind::FalseUnwind { .. }
+                | MirTerminatorKind::InlineAsm { .. } => {}
             }
         }
     }
 }
 
 // TODO: new test
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(from: u32, until: u32) -> Range {
+        Range::new(Loc(from), Loc(until)).unwrap()
+    }
+
+    fn place(id: u32) -> MirPlace {
+        MirPlace {
+            local: FnLocal::new(id, 0),
+            projection: Vec::new(),
+        }
+    }
+
+    fn decl(id: u32, name: &str, ty: &str, lives: Vec<Range>) -> MirDecl {
+        MirDecl::User {
+            local: FnLocal::new(id, 0),
+            name: name.to_owned(),
+            span: range(0, 1),
+            ty: MirType {
+                name: ty.to_owned(),
+                reference: None,
+            },
+            lives,
+            field_borrows: Vec::new(),
+            drop: false,
+            drop_range: Vec::new(),
+            definitely_live_at: Vec::new(),
+            maybe_init_at: Vec::new(),
+            must_live_at: Vec::new(),
+            storage_range: Vec::new(),
+        }
+    }
+
+    fn function(
+        statements: Vec<MirStatement>,
+        decls: Vec<MirDecl>,
+        loans: Vec<MirLoan>,
+    ) -> Function {
+        Function {
+            fn_id: 0,
+            name: "f".to_owned(),
+            basic_blocks: vec![MirBasicBlock {
+                statements,
+                terminator: MirTerminator {
+                    kind: MirTerminatorKind::Return,
+                    range: None,
+                },
+            }],
+            decls,
+            closure: None,
+            fields: Vec::new(),
+            loans,
+            conflicts: Vec::new(),
+            lifetimes: Vec::new(),
+        }
+    }
+
+    fn hover_texts(calc: CalcDecos) -> Vec<String> {
+        calc.decorations()
+            .into_iter()
+            .filter_map(|deco| match deco {
+                Deco::MutBorrow { hover_text, .. } | Deco::ImmBorrow { hover_text, .. } => {
+                    Some(hover_text)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn holders_at_cursor() {
+        // let r = &mut v; let r2 = r;
+        let func = function(
+            vec![
+                MirStatement {
+                    kind: MirStatementKind::Assign {
+                        place: place(1),
+                        rval: MirRval::Ref {
+                            place: place(0),
+                            mutable: true,
+                        },
+                    },
+                    range: Some(range(10, 16)),
+                },
+                MirStatement {
+                    kind: MirStatementKind::Assign {
+                        place: place(2),
+                        rval: MirRval::Use {
+                            operand: MirOperand::Move { place: place(1) },
+                        },
+                    },
+                    range: None,
+                },
+            ],
+            vec![
+                decl(0, "v", "Vec<i32>", vec![range(0, 60)]),
+                decl(1, "r", "&mut Vec<i32>", vec![range(10, 20)]),
+                decl(2, "r2", "&mut Vec<i32>", vec![range(20, 40)]),
+            ],
+            vec![MirLoan {
+                id: 0,
+                borrowed: place(0),
+                holder: FnLocal::new(1, 0),
+                mutable: true,
+                range: Some(range(10, 16)),
+                chain: Vec::new(),
+            }],
+        );
+
+        let mut calc = CalcDecos::new([FnLocal::new(0, 0)]);
+        utils::mir_visit(&func, &mut calc);
+        assert_eq!(
+            hover_texts(calc),
+            vec!["mutable borrow held by `r`, then `r2`"]
+        );
+
+        let mut calc = CalcDecos::new([FnLocal::new(0, 0)]);
+        calc.set_cursor(Loc(30));
+        utils::mir_visit(&func, &mut calc);
+        assert_eq!(
+            hover_texts(calc),
+            vec!["mutable borrow currently held by `r2`"]
+        );
+
+        // no holder is live after the last use, so the chain is shown
+        let mut calc = CalcDecos::new([FnLocal::new(0, 0)]);
+        calc.set_cursor(Loc(50));
+        utils::mir_visit(&func, &mut calc);
+        assert_eq!(
+            hover_texts(calc),
+            vec!["mutable borrow held by `r`, then `r2`"]
+        );
+    }
+
+    /// Hover texts of the conflict decorations for a conflict with a borrow of `v`
+    fn conflict_texts(mutable: bool, access: MirAccessKind) -> Vec<String> {
+        conflict_texts_used(mutable, access, range(30, 36))
+    }
+
+    fn conflict_texts_used(mutable: bool, access: MirAccessKind, used: Range) -> Vec<String> {
+        let mut func = function(
+            Vec::new(),
+            vec![decl(0, "v", "Vec<i32>", Vec::new())],
+            vec![MirLoan {
+                id: 0,
+                borrowed: place(0),
+                holder: FnLocal::new(1, 0),
+                mutable,
+                range: Some(range(10, 16)),
+                chain: Vec::new(),
+            }],
+        );
+        func.conflicts = vec![MirConflict {
+            loan: 0,
+            access,
+            range: range(20, 26),
+            used: Some(used),
+        }];
+        let mut calc = CalcDecos::new([FnLocal::new(0, 0)]);
+        utils::mir_visit(&func, &mut calc);
+        calc.decorations()
+            .into_iter()
+            .filter_map(|deco| match deco {
+                Deco::Conflict { hover_text, .. } => Some(hover_text),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn conflict_error_codes() {
+        assert_eq!(
+            conflict_texts(true, MirAccessKind::MutableBorrow),
+            vec![
+                "first mutable borrow occurs here (E0499)",
+                "second mutable borrow occurs here (E0499: cannot borrow `v` as mutable more than once at a time)",
+                "first borrow later used here (E0499)",
+            ]
+        );
+        assert_eq!(
+            conflict_texts(false, MirAccessKind::MutableBorrow),
+            vec![
+                "immutable borrow occurs here (E0502)",
+                "mutable borrow occurs here (E0502: cannot borrow `v` as mutable because it is also borrowed as immutable)",
+                "immutable borrow later used here (E0502)",
+            ]
+        );
+        assert_eq!(
+            conflict_texts(true, MirAccessKind::SharedBorrow),
+            vec![
+                "mutable borrow occurs here (E0502)",
+                "immutable borrow occurs here (E0502: cannot borrow `v` as immutable because it is also borrowed as mutable)",
+                "mutable borrow later used here (E0502)",
+            ]
+        );
+        assert_eq!(
+            conflict_texts(false, MirAccessKind::Move),
+            vec![
+                "borrow of `v` occurs here (E0505)",
+                "move out of `v` occurs here (E0505: cannot move out of `v` because it is borrowed)",
+                "borrow later used here (E0505)",
+            ]
+        );
+        assert_eq!(
+            conflict_texts(false, MirAccessKind::Assign),
+            vec![
+                "`v` is borrowed here (E0506)",
+                "`v` is assigned to here but it was already borrowed (E0506: cannot assign to `v` because it is borrowed)",
+                "borrow later used here (E0506)",
+            ]
+        );
+        // `r = &mut v` at 10..16 and `use(r)` at 0..6 in a loop, used again after `v.push()`
+        assert_eq!(
+            conflict_texts_used(true, MirAccessKind::MutableBorrow, range(0, 6)),
+            vec![
+                "first mutable borrow occurs here (E0499)",
+                "second mutable borrow occurs here (E0499: cannot borrow `v` as mutable more than once at a time)",
+                "first borrow later used here, in later iteration of loop (E0499)",
+            ]
+        );
+        // shown by the outlive decoration instead
+        assert!(conflict_texts(false, MirAccessKind::Drop).is_empty());
+    }
+
+    /// `fn f<'a>(x: &'a Vec<i32>) -> &'a i32 { let first = &x[0]; first }`
+    fn lifetime_function() -> Function {
+        let mut func = function(
+            Vec::new(),
+            vec![
+                decl(1, "x", "&Vec<i32>", Vec::new()),
+                decl(2, "first", "&i32", Vec::new()),
+            ],
+            Vec::new(),
+        );
+        func.lifetimes = vec![MirLifetime {
+            name: "'a".to_owned(),
+            spans: vec![range(5, 7), range(12, 14), range(32, 34)],
+            constrained: vec![
+                MirConstrainedLocal {
+                    local: FnLocal::new(1, 0),
+                    ranges: vec![range(10, 24), range(45, 50)],
+                },
+                MirConstrainedLocal {
+                    local: FnLocal::new(2, 0),
+                    ranges: vec![range(55, 62)],
+                },
+                MirConstrainedLocal {
+                    local: FnLocal::new(0, 0),
+                    ranges: vec![range(60, 62)],
+                },
+            ],
+        }];
+        func
+    }
+
+    #[test]
+    fn select_lifetime_parameter() {
+        let func = lifetime_function();
+        // where it is declared and where it is used in the signature
+        for pos in [6, 13] {
+            let mut selected = SelectLocal::new(Loc(pos));
+            utils::mir_visit(&func, &mut selected);
+            assert_eq!(selected.selected_lifetime(), Some((0, "'a")));
+        }
+        let mut selected = SelectLocal::new(Loc(20));
+        utils::mir_visit(&func, &mut selected);
+        assert_eq!(selected.selected_lifetime(), None);
+    }
+
+    #[test]
+    fn lifetime_parameter_decorations() {
+        let func = lifetime_function();
+        let mut calc = CalcDecos::new([]);
+        calc.select_lifetime(0, "'a");
+        utils::mir_visit(&func, &mut calc);
+        let decos: Vec<_> = calc
+            .decorations()
+            .into_iter()
+            .map(|deco| match deco {
+                Deco::LifetimeParam {
+                    range, hover_text, ..
+                } => (range, hover_text),
+                deco => panic!("unexpected decoration {deco:?}"),
+            })
+            .collect();
+        assert_eq!(
+            decos,
+            vec![
+                (
+                    range(10, 24),
+                    "variable `x` holds a borrow which must outlive `'a`".to_owned()
+                ),
+                (
+                    range(45, 50),
+                    "variable `x` holds a borrow which must outlive `'a`".to_owned()
+                ),
+                (
+                    range(55, 62),
+                    "variable `first` holds a borrow which must outlive `'a`".to_owned()
+                ),
+                (
+                    range(60, 62),
+                    "anonymous variable holds a borrow which must outlive `'a`".to_owned()
+                ),
+            ]
+        );
+
+        // other functions and lifetimes are not shown
+        let mut calc = CalcDecos::new([]);
+        calc.select_lifetime(1, "'a");
+        utils::mir_visit(&func, &mut calc);
+        assert!(calc.decorations().is_empty());
+    }
+}
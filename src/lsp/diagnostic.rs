@@ -0,0 +1,116 @@
+use super::analyze::CompilerMessage;
+use super::await_points;
+use crate::{models::*, utils};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tower_lsp::lsp_types;
+
+fn lsp_range(message: &CompilerMessage) -> Option<(PathBuf, lsp_types::Range)> {
+    let span = message.spans.iter().find(|v| v.is_primary)?;
+    // compiler reports 1-based lines and columns
+    let range = lsp_types::Range {
+        start: lsp_types::Position {
+            line: span.line_start.saturating_sub(1),
+            character: span.column_start.saturating_sub(1),
+        },
+        end: lsp_types::Position {
+            line: span.line_end.saturating_sub(1),
+            character: span.column_end.saturating_sub(1),
+        },
+    };
+    Some((span.file_name.clone(), range))
+}
+
+/// Group compiler errors by the file of their primary span
+///
+/// Warnings are left to rust-analyzer; errors are surfaced so that
+/// users can see why ownership information is missing.
+pub fn compiler_errors<'a>(
+    messages: impl IntoIterator<Item = &'a CompilerMessage>,
+) -> HashMap<PathBuf, Vec<lsp_types::Diagnostic>> {
+    let mut diagnostics: HashMap<_, Vec<_>> = HashMap::new();
+    for message in messages.into_iter().filter(|v| v.is_error()) {
+        let Some((path, range)) = lsp_range(message) else {
+            continue;
+        };
+        diagnostics
+            .entry(path)
+            .or_default()
+            .push(lsp_types::Diagnostic {
+                range,
+                severity: Some(lsp_types::DiagnosticSeverity::ERROR),
+                code: message
+                    .code
+                    .as_ref()
+                    .map(|v| lsp_types::NumberOrString::String(v.code.clone())),
+                source: Some("rustowl".to_owned()),
+                message: format!("not analyzed: {}", message.message),
+                ..Default::default()
+            });
+    }
+    diagnostics
+}
+
+fn source_range(source: &str, range: Range) -> lsp_types::Range {
+    let (line, character) = utils::index_to_line_char(source, range.from());
+    let start = lsp_types::Position { line, character };
+    let (line, character) = utils::index_to_line_char(source, range.until());
+    let end = lsp_types::Position { line, character };
+    lsp_types::Range { start, end }
+}
+
+/// Variables held across `.await`, reported at their declarations
+///
+/// Lock guards and `RefCell` borrows are warned, since holding them while the future
+/// is suspended blocks other tasks; other variables are only hinted.
+pub fn held_across_await(
+    uri: &lsp_types::Url,
+    source: &str,
+    file: &File,
+) -> Vec<lsp_types::Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for func in file.items.iter().filter_map(Item::function) {
+        for held in await_points::held_across_await(func) {
+            let (severity, message) = if held.is_guard() {
+                (
+                    lsp_types::DiagnosticSeverity::WARNING,
+                    format!(
+                        "`{}` of type `{}` is held across `.await`",
+                        held.name, held.ty
+                    ),
+                )
+            } else {
+                (
+                    lsp_types::DiagnosticSeverity::HINT,
+                    format!(
+                        "`{}` is held across `.await` and stored in the future",
+                        held.name
+                    ),
+                )
+            };
+            let related_information = held
+                .awaits
+                .iter()
+                .map(|v| lsp_types::DiagnosticRelatedInformation {
+                    location: lsp_types::Location {
+                        uri: uri.clone(),
+                        range: source_range(source, *v),
+                    },
+                    message: "held across this `.await`".to_owned(),
+                })
+                .collect();
+            diagnostics.push(lsp_types::Diagnostic {
+                range: source_range(source, held.span),
+                severity: Some(severity),
+                code: Some(lsp_types::NumberOrString::String(
+                    "held_across_await".to_owned(),
+                )),
+                source: Some("rustowl".to_owned()),
+                message,
+                related_information: Some(related_information),
+                ..Default::default()
+            });
+        }
+    }
+    diagnostics
+}
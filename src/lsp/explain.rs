@@ -0,0 +1,78 @@
+//! Why a borrow lives as long as it does
+
+use crate::{models::*, utils};
+use tower_lsp::lsp_types;
+
+/// A source range on the way from the creation of a borrow to the use which keeps it live
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct BorrowStep<R> {
+    pub range: R,
+    pub message: String,
+}
+impl BorrowStep<Range> {
+    pub fn to_lsp_range(&self, s: &str) -> BorrowStep<lsp_types::Range> {
+        let start = utils::index_to_line_char(s, self.range.from());
+        let end = utils::index_to_line_char(s, self.range.until());
+        BorrowStep {
+            range: lsp_types::Range {
+                start: lsp_types::Position {
+                    line: start.0,
+                    character: start.1,
+                },
+                end: lsp_types::Position {
+                    line: end.0,
+                    character: end.1,
+                },
+            },
+            message: self.message.clone(),
+        }
+    }
+}
+
+/// The innermost borrow created at the position
+pub fn loan_at<'a>(
+    functions: impl IntoIterator<Item = &'a Function>,
+    pos: Loc,
+) -> Option<(&'a Function, &'a MirLoan)> {
+    functions
+        .into_iter()
+        .flat_map(|func| func.loans.iter().map(move |loan| (func, loan)))
+        .filter_map(|(func, loan)| Some((func, loan, loan.range?)))
+        .filter(|(_, _, range)| range.from() <= pos && pos < range.until())
+        .min_by_key(|(_, _, range)| range.size())
+        .map(|(func, loan, _)| (func, loan))
+}
+
+/// The creation of the borrow, the references it flows into and the use which keeps it live
+pub fn explain(func: &Function, loan: &MirLoan) -> Vec<BorrowStep<Range>> {
+    let name = func.decls.iter().find_map(|decl| match decl {
+        MirDecl::User { local, name, .. } if *local == loan.borrowed.local => Some(name),
+        _ => None,
+    });
+    let borrowed = match (name, loan.mutable) {
+        (Some(name), true) => format!("`{name}` mutably borrowed here"),
+        (Some(name), false) => format!("`{name}` borrowed here"),
+        (None, true) => "mutably borrowed here".to_owned(),
+        (None, false) => "borrowed here".to_owned(),
+    };
+
+    let mut steps: Vec<_> = loan
+        .range
+        .map(|range| BorrowStep {
+            range,
+            message: borrowed,
+        })
+        .into_iter()
+        .collect();
+    if let Some((used, flows)) = loan.chain.split_last() {
+        steps.extend(flows.iter().map(|range| BorrowStep {
+            range: *range,
+            message: "the borrow flows into another reference here".to_owned(),
+        }));
+        steps.push(BorrowStep {
+            range: *used,
+            message: "used here, so the borrow must live until here".to_owned(),
+        });
+    }
+    steps
+}
@@ -0,0 +1,306 @@
+//! Support for `rust-project.json`, the project description which build systems
+//! other than Cargo (e.g. Bazel, Buck) generate for rust-analyzer.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub const PROJECT_FILE_NAME: &str = "rust-project.json";
+
+#[derive(serde::Deserialize, Clone, Debug)]
+struct ProjectJson {
+    crates: Vec<ProjectCrate>,
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct ProjectDep {
+    /// Index of the dependency in `crates`
+    #[serde(rename = "crate")]
+    pub krate: usize,
+    /// Name which the dependency is referred to by
+    pub name: String,
+}
+
+#[derive(serde::Deserialize, Clone, Debug)]
+pub struct ProjectCrate {
+    pub display_name: Option<String>,
+    pub root_module: PathBuf,
+    pub edition: String,
+    #[serde(default)]
+    pub deps: Vec<ProjectDep>,
+    #[serde(default)]
+    pub cfg: Vec<String>,
+    is_workspace_member: Option<bool>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub is_proc_macro: bool,
+    /// Prebuilt proc-macro library, used instead of building the crate
+    pub proc_macro_dylib_path: Option<PathBuf>,
+}
+
+/// Crates of a `rust-project.json`, whose paths are resolved to absolute paths
+#[derive(Clone, Debug)]
+pub struct RustProject {
+    root: PathBuf,
+    crates: Vec<ProjectCrate>,
+    build_order: Vec<usize>,
+}
+
+impl RustProject {
+    /// Find `rust-project.json` in the nearest ancestor of `dir`
+    ///
+    /// Cargo projects take precedence, so the search stops at a directory
+    /// containing `Cargo.toml`.
+    pub fn find(dir: &Path) -> Option<PathBuf> {
+        for ancestor in dir.ancestors() {
+            let project = ancestor.join(PROJECT_FILE_NAME);
+            if project.is_file() {
+                return Some(project);
+            }
+            if ancestor.join("Cargo.toml").is_file() {
+                return None;
+            }
+        }
+        None
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let root = path.parent().unwrap().to_path_buf();
+        let data = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::parse(root, &data)
+    }
+
+    /// Parse the content of `rust-project.json` placed in `root`
+    fn parse(root: PathBuf, data: &str) -> Result<Self, String> {
+        let project: ProjectJson = serde_json::from_str(data).map_err(|e| e.to_string())?;
+
+        let mut crates = project.crates;
+        for krate in &mut crates {
+            krate.root_module = root.join(&krate.root_module);
+            if let Some(dylib) = &mut krate.proc_macro_dylib_path {
+                *dylib = root.join(&*dylib);
+            }
+        }
+        let build_order = build_order(&crates)?;
+        Ok(Self {
+            root,
+            crates,
+            build_order,
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Crate indices sorted so that dependencies come first
+    pub fn build_order(&self) -> &[usize] {
+        &self.build_order
+    }
+
+    pub fn crate_name(&self, index: usize) -> String {
+        let name = self.crates[index]
+            .display_name
+            .clone()
+            .or_else(|| {
+                // fall back to the name which dependents refer to
+                self.crates
+                    .iter()
+                    .flat_map(|v| &v.deps)
+                    .find(|v| v.krate == index)
+                    .map(|v| v.name.clone())
+            })
+            .unwrap_or_else(|| format!("crate{index}"));
+        name.replace('-', "_")
+    }
+
+    /// Crates whose root module is in the project are members unless specified
+    pub fn is_workspace_member(&self, index: usize) -> bool {
+        let krate = &self.crates[index];
+        krate
+            .is_workspace_member
+            .unwrap_or_else(|| krate.root_module.starts_with(&self.root))
+    }
+
+    /// Compiled library of the crate, passed to its dependents by `--extern`
+    fn artifact(&self, index: usize, out_dir: &Path) -> PathBuf {
+        let krate = &self.crates[index];
+        let name = self.crate_name(index);
+        if let Some(dylib) = &krate.proc_macro_dylib_path {
+            dylib.clone()
+        } else if krate.is_proc_macro {
+            out_dir.join(format!(
+                "{}{name}-{index}{}",
+                std::env::consts::DLL_PREFIX,
+                std::env::consts::DLL_SUFFIX
+            ))
+        } else {
+            out_dir.join(format!("lib{name}-{index}.rmeta"))
+        }
+    }
+
+    /// Whether the crate has to be compiled before its dependents
+    pub fn needs_build(&self, index: usize) -> bool {
+        self.crates[index].proc_macro_dylib_path.is_none()
+    }
+
+    pub fn env(&self, index: usize) -> &HashMap<String, String> {
+        &self.crates[index].env
+    }
+
+    /// Arguments passed to rustc to compile the crate into `out_dir`
+    pub fn rustc_args(&self, index: usize, out_dir: &Path) -> Vec<String> {
+        let krate = &self.crates[index];
+        let mut args = vec![
+            format!("--crate-name={}", self.crate_name(index)),
+            format!("--edition={}", krate.edition),
+            format!("--out-dir={}", out_dir.display()),
+            format!("-Cextra-filename=-{index}"),
+            format!("-Ldependency={}", out_dir.display()),
+            "--error-format=json".to_owned(),
+        ];
+        if krate.is_proc_macro {
+            args.extend([
+                "--crate-type=proc-macro".to_owned(),
+                "--emit=link".to_owned(),
+                "--extern=proc_macro".to_owned(),
+            ]);
+        } else {
+            args.extend(["--crate-type=lib".to_owned(), "--emit=metadata".to_owned()]);
+        }
+        for cfg in &krate.cfg {
+            args.push(format!("--cfg={cfg}"));
+        }
+        for dep in &krate.deps {
+            args.push(format!(
+                "--extern={}={}",
+                dep.name.replace('-', "_"),
+                self.artifact(dep.krate, out_dir).display()
+            ));
+        }
+        args.push(krate.root_module.display().to_string());
+        args
+    }
+}
+
+fn build_order(crates: &[ProjectCrate]) -> Result<Vec<usize>, String> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        Unvisited,
+        Visiting,
+        Visited,
+    }
+    fn visit(
+        crates: &[ProjectCrate],
+        index: usize,
+        states: &mut [State],
+        order: &mut Vec<usize>,
+    ) -> Result<(), String> {
+        match states[index] {
+            State::Visited => return Ok(()),
+            State::Visiting => return Err(format!("dependency cycle at crate {index}")),
+            State::Unvisited => {}
+        }
+        states[index] = State::Visiting;
+        for dep in &crates[index].deps {
+            if crates.len() <= dep.krate {
+                return Err(format!(
+                    "crate {index} depends on unknown crate {}",
+                    dep.krate
+                ));
+            }
+            visit(crates, dep.krate, states, order)?;
+        }
+        states[index] = State::Visited;
+        order.push(index);
+        Ok(())
+    }
+
+    let mut states = vec![State::Unvisited; crates.len()];
+    let mut order = Vec::with_capacity(crates.len());
+    for index in 0..crates.len() {
+        visit(crates, index, &mut states, &mut order)?;
+    }
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(crates: &str) -> Result<RustProject, String> {
+        RustProject::parse(
+            PathBuf::from("/project"),
+            &format!(r#"{{ "crates": {crates} }}"#),
+        )
+    }
+
+    #[test]
+    fn dependencies_come_first() {
+        let project = project(
+            r#"[
+                { "root_module": "app/main.rs", "edition": "2021",
+                  "deps": [{ "crate": 2, "name": "util" }, { "crate": 1, "name": "core-lib" }] },
+                { "root_module": "core/lib.rs", "edition": "2021" },
+                { "root_module": "util/lib.rs", "edition": "2021",
+                  "deps": [{ "crate": 1, "name": "core-lib" }] }
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(project.build_order(), [1, 2, 0]);
+    }
+
+    #[test]
+    fn dependency_cycle() {
+        let result = project(
+            r#"[
+                { "root_module": "a.rs", "edition": "2021", "deps": [{ "crate": 1, "name": "b" }] },
+                { "root_module": "b.rs", "edition": "2021", "deps": [{ "crate": 0, "name": "a" }] }
+            ]"#,
+        );
+        assert_eq!(result.unwrap_err(), "dependency cycle at crate 0");
+
+        let result = project(
+            r#"[{ "root_module": "a.rs", "edition": "2021", "deps": [{ "crate": 3, "name": "b" }] }]"#,
+        );
+        assert_eq!(result.unwrap_err(), "crate 0 depends on unknown crate 3");
+    }
+
+    #[test]
+    fn rustc_args() {
+        let project = project(
+            r#"[
+                { "display_name": "my-app", "root_module": "src/main.rs", "edition": "2024",
+                  "cfg": ["feature=\"std\"", "test"],
+                  "deps": [{ "crate": 1, "name": "my-lib" }, { "crate": 2, "name": "derive" }] },
+                { "root_module": "lib/lib.rs", "edition": "2018" },
+                { "root_module": "derive/lib.rs", "edition": "2021", "is_proc_macro": true,
+                  "proc_macro_dylib_path": "prebuilt/libderive.so" }
+            ]"#,
+        )
+        .unwrap();
+        let out_dir = Path::new("/out");
+        assert_eq!(
+            project.rustc_args(0, out_dir),
+            [
+                "--crate-name=my_app",
+                "--edition=2024",
+                "--out-dir=/out",
+                "-Cextra-filename=-0",
+                "-Ldependency=/out",
+                "--error-format=json",
+                "--crate-type=lib",
+                "--emit=metadata",
+                "--cfg=feature=\"std\"",
+                "--cfg=test",
+                "--extern=my_lib=/out/libmy_lib-1.rmeta",
+                "--extern=derive=/project/prebuilt/libderive.so",
+                "/project/src/main.rs",
+            ]
+        );
+        // named after the dependents, and a prebuilt proc macro is not compiled
+        assert_eq!(project.crate_name(1), "my_lib");
+        assert!(project.needs_build(1));
+        assert!(!project.needs_build(2));
+    }
+}
@@ -0,0 +1,178 @@
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// Priority of an analysis job; jobs with higher priority start first
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum JobPriority {
+    /// Workspaces without the focused document
+    Background,
+    /// The rest of the workspace containing the focused document
+    FocusedWorkspace,
+    /// The package containing the focused document
+    FocusedPackage,
+}
+
+/// Numbers of running and waiting jobs
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct QueueState {
+    pub limit: usize,
+    pub running: usize,
+    pub queued: usize,
+}
+impl QueueState {
+    /// Whether a new job has to wait for running jobs
+    pub fn is_full(&self) -> bool {
+        self.limit <= self.running
+    }
+}
+impl fmt::Display for QueueState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} running, {} queued",
+            self.running, self.limit, self.queued
+        )
+    }
+}
+
+type Ticket = (Reverse<JobPriority>, u64);
+
+struct State {
+    limit: usize,
+    running: usize,
+    next_id: u64,
+    /// ordered by priority, then by arrival
+    waiting: BTreeSet<Ticket>,
+}
+
+/// Queue of analysis jobs which limits the number of jobs running at once
+pub struct Scheduler {
+    state: Mutex<State>,
+    notify: Notify,
+}
+
+impl Scheduler {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            state: Mutex::new(State {
+                limit: limit.max(1),
+                running: 0,
+                next_id: 0,
+                waiting: BTreeSet::new(),
+            }),
+            notify: Notify::new(),
+        }
+    }
+
+    pub fn set_limit(&self, limit: usize) {
+        self.state.lock().unwrap().limit = limit.max(1);
+        self.notify.notify_waiters();
+    }
+
+    pub fn state(&self) -> QueueState {
+        let state = self.state.lock().unwrap();
+        QueueState {
+            limit: state.limit,
+            running: state.running,
+            queued: state.waiting.len(),
+        }
+    }
+
+    /// Wait until the job can start
+    ///
+    /// The job keeps its slot until the returned permit is dropped.
+    pub async fn acquire(self: &Arc<Self>, priority: JobPriority) -> JobPermit {
+        let ticket = {
+            let mut state = self.state.lock().unwrap();
+            let ticket = (Reverse(priority), state.next_id);
+            state.next_id += 1;
+            state.waiting.insert(ticket);
+            ticket
+        };
+        // withdraw the ticket if the job is cancelled while waiting
+        let mut waiting = WaitingTicket {
+            scheduler: self,
+            ticket: Some(ticket),
+        };
+        loop {
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.running < state.limit && state.waiting.first() == Some(&ticket) {
+                    state.waiting.remove(&ticket);
+                    state.running += 1;
+                    waiting.ticket = None;
+                    // the next job may also start if the limit allows
+                    self.notify.notify_waiters();
+                    return JobPermit {
+                        scheduler: self.clone(),
+                    };
+                }
+            }
+            notified.await;
+        }
+    }
+}
+
+struct WaitingTicket<'a> {
+    scheduler: &'a Scheduler,
+    ticket: Option<Ticket>,
+}
+impl Drop for WaitingTicket<'_> {
+    fn drop(&mut self) {
+        if let Some(ticket) = self.ticket.take() {
+            self.scheduler.state.lock().unwrap().waiting.remove(&ticket);
+            self.scheduler.notify.notify_waiters();
+        }
+    }
+}
+
+/// Slot of a running job
+pub struct JobPermit {
+    scheduler: Arc<Scheduler>,
+}
+impl Drop for JobPermit {
+    fn drop(&mut self) {
+        self.scheduler.state.lock().unwrap().running -= 1;
+        self.scheduler.notify.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn higher_priority_job_starts_first() {
+        let scheduler = Arc::new(Scheduler::new(1));
+        let running = scheduler.acquire(JobPriority::Background).await;
+
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        for priority in [JobPriority::Background, JobPriority::FocusedPackage] {
+            let scheduler = scheduler.clone();
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                let _permit = scheduler.acquire(priority).await;
+                sender.send(priority).unwrap();
+            });
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(
+            scheduler.state(),
+            QueueState {
+                limit: 1,
+                running: 1,
+                queued: 2
+            }
+        );
+
+        drop(running);
+        assert_eq!(receiver.recv().await, Some(JobPriority::FocusedPackage));
+        assert_eq!(receiver.recv().await, Some(JobPriority::Background));
+    }
+}
@@ -0,0 +1,138 @@
+//! Compile options of a Rust source file which does not belong to any project
+
+use std::ffi::OsString;
+use std::path::Path;
+
+/// Whether the source starts with a frontmatter containing a Cargo manifest,
+/// which makes the file a cargo script
+///
+/// ```text
+/// #!/usr/bin/env cargo
+/// ---cargo
+/// [dependencies]
+/// ---
+/// ```
+pub fn has_cargo_frontmatter(source: &str) -> bool {
+    let mut lines = source.lines().peekable();
+    // shebang, not to be confused with an inner attribute `#![...]`
+    if let Some(line) = lines.peek()
+        && line.starts_with("#!")
+        && !line[2..].trim_start().starts_with('[')
+    {
+        lines.next();
+    }
+    let Some(opening) = lines.find(|v| !v.trim().is_empty()) else {
+        return false;
+    };
+    let dashes = opening.chars().take_while(|v| *v == '-').count();
+    if dashes < 3 {
+        return false;
+    }
+    let infostring = opening[dashes..].trim();
+    infostring.is_empty() || infostring == "cargo"
+}
+
+/// Edition of a single file, as `cargo new` would create
+pub const DEFAULT_EDITION: &str = "2024";
+
+/// Whether the source defines `fn main` at the top level
+pub fn has_main(source: &str) -> bool {
+    let mut parser = tree_sitter::Parser::new();
+    if parser
+        .set_language(&tree_sitter_rust::LANGUAGE.into())
+        .is_err()
+    {
+        return false;
+    }
+    let Some(tree) = parser.parse(source, None) else {
+        return false;
+    };
+    let root = tree.root_node();
+    let mut cursor = root.walk();
+    root.named_children(&mut cursor).any(|node| {
+        node.kind() == "function_item"
+            && node
+                .child_by_field_name("name")
+                .and_then(|v| v.utf8_text(source.as_bytes()).ok())
+                == Some("main")
+    })
+}
+
+/// `edition` of a rustfmt configuration, which is the only place a single file
+/// may declare its edition
+pub fn rustfmt_edition(config: &str) -> Option<String> {
+    let config: toml::Table = toml::from_str(config).ok()?;
+    config.get("edition")?.as_str().map(str::to_owned)
+}
+
+/// Arguments to compile the single file
+pub fn rustc_args(path: &Path, source: &str, edition: &str) -> Vec<OsString> {
+    let crate_type = if has_main(source) { "bin" } else { "lib" };
+    let mut args: Vec<OsString> = vec![
+        format!("--crate-type={crate_type}").into(),
+        format!("--edition={edition}").into(),
+        "--emit=metadata".into(),
+    ];
+    #[cfg(unix)]
+    args.push("-o/dev/null".into());
+    #[cfg(windows)]
+    args.push("-oNUL".into());
+    args.push(path.into());
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_cargo_script() {
+        assert!(has_cargo_frontmatter(
+            "#!/usr/bin/env cargo\n---cargo\n[dependencies]\n---\nfn main() {}\n"
+        ));
+        assert!(has_cargo_frontmatter("---\n---\nfn main() {}\n"));
+        assert!(!has_cargo_frontmatter("#![allow(unused)]\n---\n"));
+        assert!(!has_cargo_frontmatter("fn main() {}\n"));
+    }
+
+    #[test]
+    fn detect_main() {
+        assert!(has_main("use std::io;\n\nfn main() {}\n"));
+        assert!(has_main("async fn main () {}\n"));
+        assert!(has_main("#[inline] fn main() {}\n"));
+        assert!(has_main(
+            "#![allow(unused)]\n#[cfg(unix)]\npub fn main() {}\n"
+        ));
+        assert!(!has_main("fn main_loop() {}\n"));
+    }
+
+    #[test]
+    fn ignore_main_outside_top_level() {
+        assert!(!has_main("// fn main() {}\n"));
+        assert!(!has_main("/*\nfn main() {}\n*/\n"));
+        assert!(!has_main("const SOURCE: &str = \"\nfn main() {}\n\";\n"));
+        assert!(!has_main("mod app {\n    fn main() {}\n}\n"));
+        assert!(!has_main("impl App {\n    fn main() {}\n}\n"));
+    }
+
+    #[test]
+    fn read_rustfmt_edition() {
+        assert_eq!(
+            rustfmt_edition("max_width = 80\nedition = \"2021\"\n"),
+            Some("2021".to_owned())
+        );
+        assert_eq!(rustfmt_edition("max_width = 80\n"), None);
+    }
+
+    #[test]
+    fn command_line() {
+        let args = rustc_args(Path::new("main.rs"), "fn main() {}\n", DEFAULT_EDITION);
+        assert!(args.contains(&"--crate-type=bin".into()));
+        assert!(args.contains(&"--edition=2024".into()));
+        assert_eq!(args.last(), Some(&"main.rs".into()));
+
+        let args = rustc_args(Path::new("lib.rs"), "// fn main() {}\n", "2021");
+        assert!(args.contains(&"--crate-type=lib".into()));
+        assert!(args.contains(&"--edition=2021".into()));
+    }
+}
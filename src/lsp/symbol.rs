@@ -0,0 +1,153 @@
+use crate::{models::*, utils};
+use tower_lsp::lsp_types;
+
+fn lsp_range(source: &str, range: Range) -> lsp_types::Range {
+    let start = utils::index_to_line_char(source, range.from());
+    let end = utils::index_to_line_char(source, range.until());
+    lsp_types::Range {
+        start: lsp_types::Position {
+            line: start.0,
+            character: start.1,
+        },
+        end: lsp_types::Position {
+            line: end.0,
+            character: end.1,
+        },
+    }
+}
+
+/// Last segment of the function path
+///
+/// e.g. `module::func::{closure#0}` -> `{closure#0}`
+fn short_name(name: &str) -> &str {
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+#[allow(deprecated)]
+fn item_symbol(source: &str, item: &Item) -> lsp_types::DocumentSymbol {
+    let range = lsp_range(source, item.span());
+    let (detail, children) = match item {
+        Item::Function { mir, .. } => {
+            let mut vars: Vec<_> = mir
+                .decls
+                .iter()
+                .filter_map(|decl| match decl {
+                    MirDecl::User { name, span, ty, .. } => Some((name, *span, ty)),
+                    MirDecl::Other { .. } => None,
+                })
+                .collect();
+            vars.sort_by_key(|(_, span, _)| span.from());
+            vars.dedup_by_key(|(_, span, _)| *span);
+            let children = vars
+                .into_iter()
+                .map(|(name, span, ty)| {
+                    let range = lsp_range(source, span);
+                    lsp_types::DocumentSymbol {
+                        name: name.clone(),
+                        detail: Some(ty.name.clone()),
+                        kind: lsp_types::SymbolKind::VARIABLE,
+                        tags: None,
+                        deprecated: None,
+                        range,
+                        selection_range: range,
+                        children: None,
+                    }
+                })
+                .collect();
+            (Some(item.status().to_string()), children)
+        }
+        Item::Skipped { .. } => (Some(item.status().to_string()), Vec::new()),
+    };
+    lsp_types::DocumentSymbol {
+        name: short_name(item.name()).to_owned(),
+        detail,
+        kind: lsp_types::SymbolKind::FUNCTION,
+        tags: None,
+        deprecated: None,
+        range,
+        selection_range: range,
+        children: Some(children),
+    }
+}
+
+/// Insert `symbol` into the innermost symbol of `symbols` whose range contains it
+fn insert_nested(symbols: &mut Vec<lsp_types::DocumentSymbol>, symbol: lsp_types::DocumentSymbol) {
+    let contains = |outer: &lsp_types::Range, inner: &lsp_types::Range| {
+        outer.start <= inner.start && inner.end <= outer.end && outer != inner
+    };
+    for parent in symbols.iter_mut() {
+        if parent.kind == lsp_types::SymbolKind::FUNCTION && contains(&parent.range, &symbol.range)
+        {
+            insert_nested(parent.children.get_or_insert_with(Vec::new), symbol);
+            return;
+        }
+    }
+    symbols.push(symbol);
+}
+
+/// Build document symbols of analyzed functions in a file
+///
+/// Nested bodies such as closures are placed under the function that contains them,
+/// and user variables are listed as children of each function.
+pub fn document_symbols(source: &str, file: &File) -> Vec<lsp_types::DocumentSymbol> {
+    let mut items: Vec<_> = file.items.iter().collect();
+    // outer functions come first so that nested ones can find their parent
+    items.sort_by_key(|v| (v.span().from(), std::cmp::Reverse(v.span().until())));
+    items.dedup_by_key(|v| v.fn_id());
+
+    let mut symbols = Vec::new();
+    for item in items {
+        insert_nested(&mut symbols, item_symbol(source, item));
+    }
+    symbols
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(from: u32, until: u32) -> Range {
+        Range::new(Loc(from), Loc(until)).unwrap()
+    }
+
+    #[test]
+    fn closures_are_nested_in_parent_function() {
+        let source = "fn f() {\n    let c = || {};\n}\n";
+        let file = File {
+            items: vec![
+                Item::Function {
+                    span: range(13, 27),
+                    mir: Function {
+                        fn_id: 1,
+                        name: "f::{closure#0}".to_owned(),
+                        basic_blocks: Vec::new(),
+                        decls: Vec::new(),
+                        closure: None,
+                        fields: Vec::new(),
+                        loans: Vec::new(),
+                        conflicts: Vec::new(),
+                        lifetimes: Vec::new(),
+                    },
+                    cached: true,
+                },
+                Item::Skipped {
+                    span: range(0, 30),
+                    fn_id: 0,
+                    name: "f".to_owned(),
+                    reason: SkipReason::CompileError,
+                },
+            ],
+        };
+        let symbols = document_symbols(source, &file);
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].name, "f");
+        assert_eq!(
+            symbols[0].detail.as_deref(),
+            Some("not analyzed: compile error")
+        );
+        let children = symbols[0].children.as_ref().unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].name, "{closure#0}");
+        assert_eq!(children[0].detail.as_deref(), Some("analyzed (cached)"));
+    }
+}
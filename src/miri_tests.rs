@@ -223,6 +223,11 @@ mod miri_memory_safety_tests {
             name: String::from("test_function"),
             basic_blocks: Vec::new(),
             decls: Vec::new(),
+            closure: None,
+            fields: Vec::new(),
+            loans: Vec::new(),
+            conflicts: Vec::new(),
+            lifetimes: Vec::new(),
         };
 
         // Test cloning of complex nested structures
@@ -246,6 +251,11 @@ mod miri_memory_safety_tests {
                 name: format!("function_{i}"),
                 basic_blocks: Vec::new(),
                 decls: Vec::new(),
+                closure: None,
+                fields: Vec::new(),
+                loans: Vec::new(),
+                conflicts: Vec::new(),
+                lifetimes: Vec::new(),
             });
         }
 
@@ -258,6 +268,11 @@ mod miri_memory_safety_tests {
             name: String::from("large_function"),
             basic_blocks: Vec::with_capacity(1000),
             decls: Vec::with_capacity(500),
+            closure: None,
+            fields: Vec::new(),
+            loans: Vec::new(),
+            conflicts: Vec::new(),
+            lifetimes: Vec::new(),
         };
 
         assert!(large_function.basic_blocks.capacity() >= 1000);
@@ -15,7 +15,7 @@ impl FnLocal {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 #[serde(transparent)]
 pub struct Loc(pub u32);
 impl Loc {
@@ -74,7 +74,7 @@ impl From<Loc> for u32 {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub struct Range {
     from: Loc,
     until: Loc,
@@ -149,18 +149,136 @@ impl MirVariables {
     }
 }
 
+/// Why a function has no analysis result
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// The body has type errors, so borrow checking facts are not available
+    CompileError,
+    /// The analysis of the body panicked
+    AnalysisFailed,
+    /// The analysis did not finish within the time budget
+    TimedOut,
+    /// The analysis was aborted because the memory budget was exceeded
+    MemoryLimitExceeded,
+    /// The source of the body is not available, e.g. it is expanded from a macro of
+    /// another crate; the function is reported at the macro call site
+    NoSource,
+}
+impl SkipReason {
+    /// Whether the function was skipped because of the analysis budget
+    pub fn is_over_budget(self) -> bool {
+        matches!(self, SkipReason::TimedOut | SkipReason::MemoryLimitExceeded)
+    }
+}
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            SkipReason::CompileError => "compile error",
+            SkipReason::AnalysisFailed => "analysis panicked",
+            SkipReason::TimedOut => "timed out",
+            SkipReason::MemoryLimitExceeded => "memory limit exceeded",
+            SkipReason::NoSource => "no source",
+        };
+        f.write_str(text)
+    }
+}
+
+/// Outcome of the analysis of a function
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FunctionStatus {
+    Analyzed,
+    Cached,
+    Skipped(SkipReason),
+}
+impl std::fmt::Display for FunctionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FunctionStatus::Analyzed => f.write_str("analyzed"),
+            FunctionStatus::Cached => f.write_str("analyzed (cached)"),
+            FunctionStatus::Skipped(reason) => write!(f, "not analyzed: {reason}"),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum Item {
-    Function { span: Range, mir: Function },
+    Function {
+        span: Range,
+        mir: Function,
+        /// The result was reused from the incremental cache
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        cached: bool,
+    },
+    Skipped {
+        span: Range,
+        fn_id: u32,
+        name: String,
+        reason: SkipReason,
+    },
+}
+
+impl Item {
+    pub fn fn_id(&self) -> u32 {
+        match self {
+            Item::Function { mir, .. } => mir.fn_id,
+            Item::Skipped { fn_id, .. } => *fn_id,
+        }
+    }
+    pub fn name(&self) -> &str {
+        match self {
+            Item::Function { mir, .. } => &mir.name,
+            Item::Skipped { name, .. } => name,
+        }
+    }
+    pub fn span(&self) -> Range {
+        match self {
+            Item::Function { span, .. } | Item::Skipped { span, .. } => *span,
+        }
+    }
+    pub fn status(&self) -> FunctionStatus {
+        match self {
+            Item::Function { cached: false, .. } => FunctionStatus::Analyzed,
+            Item::Function { cached: true, .. } => FunctionStatus::Cached,
+            Item::Skipped { reason, .. } => FunctionStatus::Skipped(*reason),
+        }
+    }
+    /// Returns the analyzed function, or `None` if the function was skipped
+    pub fn function(&self) -> Option<&Function> {
+        match self {
+            Item::Function { mir, .. } => Some(mir),
+            Item::Skipped { .. } => None,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct File {
-    pub items: Vec<Function>,
+    pub items: Vec<Item>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+impl File {
+    /// Merge the items of another analysis, which replace the items of the same function
+    pub fn merge(&mut self, other: Self) {
+        let mut index: HashMap<_, _> = self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (item.fn_id(), i))
+            .collect();
+        for item in other.items {
+            if let Some(&i) = index.get(&item.fn_id()) {
+                self.items[i] = item;
+            } else {
+                index.insert(item.fn_id(), self.items.len());
+                self.items.push(item);
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
 #[serde(transparent)]
 pub struct Workspace(pub HashMap<String, Crate>);
 
@@ -186,8 +304,7 @@ impl Crate {
         let Crate(files) = other;
         for (file, mir) in files {
             if let Some(insert) = self.0.get_mut(&file) {
-                insert.items.extend_from_slice(&mir.items);
-                insert.items.dedup_by(|a, b| a.fn_id == b.fn_id);
+                insert.merge(mir);
             } else {
                 self.0.insert(file, mir);
             }
@@ -209,28 +326,85 @@ pub struct MirPlace {
     pub local: FnLocal,
     pub projection: Vec<MirProjectionElem>,
 }
+impl MirPlace {
+    /// Path of the fields projected from the local, e.g. `[1, 0]` for `s.1.0` and `(*s.1.0).2`
+    pub fn fields(&self) -> Vec<usize> {
+        self.projection
+            .iter()
+            .map_while(|v| match v {
+                MirProjectionElem::Field { index } => Some(*index),
+                _ => None,
+            })
+            .collect()
+    }
+    /// Whether the place is a field of the local, rather than the whole local
+    pub fn is_field(&self) -> bool {
+        matches!(
+            self.projection.first(),
+            Some(MirProjectionElem::Field { .. })
+        )
+    }
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum MirOperand {
-    Copy { place: MirPlace },
-    Move { place: MirPlace },
-    // TODO: Constant, RuntimeChecks
-    Other,
+    Copy {
+        place: MirPlace,
+    },
+    Move {
+        place: MirPlace,
+    },
+    Constant,
+    /// Whether UB, contract or overflow checks are enabled in the current crate
+    RuntimeChecks,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum MirRval {
-    Use { operand: MirOperand },
-    Repeat { operand: MirOperand },
-    Ref { place: MirPlace, mutable: bool },
-    Cast { operand: MirOperand },
-    BinaryOp { left: MirOperand, right: MirOperand },
-    UnaryOp { operand: MirOperand },
-    Aggregate { fields: Vec<MirOperand> },
-    // TODO: ThreadLocalRef, RawPtr, Discriminant, CopyForDeref, WrapUnsafeBinder, Reborrow
-    Other,
+    Use {
+        operand: MirOperand,
+    },
+    Repeat {
+        operand: MirOperand,
+    },
+    Ref {
+        place: MirPlace,
+        mutable: bool,
+    },
+    Cast {
+        operand: MirOperand,
+    },
+    BinaryOp {
+        left: MirOperand,
+        right: MirOperand,
+    },
+    UnaryOp {
+        operand: MirOperand,
+    },
+    Aggregate {
+        fields: Vec<MirOperand>,
+    },
+    ThreadLocalRef,
+    /// `&raw const place` or `&raw mut place`
+    RawPtr {
+        place: MirPlace,
+        mutable: bool,
+    },
+    /// Pointer created only to read the metadata of the place, e.g. the length of a slice
+    PtrMetadata {
+        place: MirPlace,
+    },
+    Discriminant {
+        place: MirPlace,
+    },
+    CopyForDeref {
+        place: MirPlace,
+    },
+    WrapUnsafeBinder {
+        operand: MirOperand,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -243,15 +417,46 @@ pub struct MirStatement {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum MirStatementKind {
-    Assign { place: MirPlace, rval: MirRval },
-    StorageLive { local: FnLocal },
-    StorageDead { local: FnLocal },
+    Assign {
+        place: MirPlace,
+        rval: MirRval,
+    },
+    /// Read for borrow checking only, which does not move the place
+    FakeRead {
+        place: MirPlace,
+        cause: MirFakeReadCause,
+    },
+    SetDiscriminant {
+        place: MirPlace,
+    },
+    StorageLive {
+        local: FnLocal,
+    },
+    StorageDead {
+        local: FnLocal,
+    },
+    /// Place evaluated but not read, e.g. `x` of `let _ = x;`
+    PlaceMention {
+        place: MirPlace,
+    },
     Nop,
-    // TODO: FakeRead, SetDiscriminant, PlaceMention, AscribeUserType, Coverage, ConstEvalCounter,
+    // TODO: Retag, AscribeUserType, Coverage, Intrinsic, ConstEvalCounter,
     // BackwardIncompatibleDropHint
     Other,
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum MirFakeReadCause {
+    /// Matched place of `match` or `if let`
+    MatchedPlace,
+    MatchGuard,
+    GuardBinding,
+    /// Variable bound by `let` with a trivial pattern
+    Let,
+    Index,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct MirTerminator {
     #[serde(flatten)]
@@ -291,9 +496,30 @@ pub enum MirTerminatorKind {
         cond: MirOperand,
         target: BasicBlockId,
     },
-    // TODO: UnwindResume, UnwindTerminate, Yield, CoroutineDrop, FalseEdge, FalseUnwind, InlineAsm
-    Other {
-        successors: Vec<BasicBlockId>,
+    /// Suspension point of a coroutine, e.g. `.await`
+    ///
+    /// The coroutine continues from `resume` with the resumed value stored in `resume_arg`,
+    /// or from `drop` when it is dropped while suspended.
+    Yield {
+        value: MirOperand,
+        resume: BasicBlockId,
+        resume_arg: MirPlace,
+        drop: Option<BasicBlockId>,
+    },
+    CoroutineDrop,
+    UnwindResume,
+    UnwindTerminate,
+    /// Edge to `imaginary_target` exists only for borrow checking, e.g. between match arms
+    FalseEdge {
+        real_target: BasicBlockId,
+        imaginary_target: BasicBlockId,
+    },
+    /// Loop header, which may unwind only for borrow checking
+    FalseUnwind {
+        real_target: BasicBlockId,
+    },
+    InlineAsm {
+        targets: Vec<BasicBlockId>,
     },
 }
 impl MirTerminator {
@@ -304,10 +530,21 @@ impl MirTerminator {
             MirTerminatorKind::Drop { target, .. } => vec![*target],
             MirTerminatorKind::Call { target, .. } => (*target).into_iter().collect(),
             MirTerminatorKind::Assert { target, .. } => vec![*target],
-            MirTerminatorKind::Other { successors } => successors.clone(),
+            MirTerminatorKind::Yield { resume, drop, .. } => {
+                std::iter::once(*resume).chain(*drop).collect()
+            }
+            MirTerminatorKind::FalseEdge {
+                real_target,
+                imaginary_target,
+            } => vec![*real_target, *imaginary_target],
+            MirTerminatorKind::FalseUnwind { real_target } => vec![*real_target],
+            MirTerminatorKind::InlineAsm { targets } => targets.clone(),
             MirTerminatorKind::TailCall { .. }
             | MirTerminatorKind::Return
-            | MirTerminatorKind::Unreachable => Vec::new(),
+            | MirTerminatorKind::Unreachable
+            | MirTerminatorKind::CoroutineDrop
+            | MirTerminatorKind::UnwindResume
+            | MirTerminatorKind::UnwindTerminate => Vec::new(),
         }
     }
 }
@@ -342,8 +579,9 @@ pub enum MirDecl {
         span: Range,
         ty: MirType,
         lives: Vec<Range>,
-        shared_borrow: Vec<Range>,
-        mutable_borrow: Vec<Range>,
+        /// Borrows of the variable and its fields, by the borrowed field path
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        field_borrows: Vec<MirFieldBorrow>,
         drop: bool,
         drop_range: Vec<Range>,
         definitely_live_at: Vec<Range>,
@@ -356,8 +594,9 @@ pub enum MirDecl {
         local: FnLocal,
         ty: MirType,
         lives: Vec<Range>,
-        shared_borrow: Vec<Range>,
-        mutable_borrow: Vec<Range>,
+        /// Borrows of the variable and its fields, by the borrowed field path
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        field_borrows: Vec<MirFieldBorrow>,
         drop: bool,
         drop_range: Vec<Range>,
         definitely_live_at: Vec<Range>,
@@ -374,4 +613,185 @@ pub struct Function {
     pub name: String,
     pub basic_blocks: Vec<MirBasicBlock>,
     pub decls: Vec<MirDecl>,
+    /// Link to the enclosing function if this is a closure or coroutine body
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub closure: Option<MirClosure>,
+    /// Field names of the places whose fields are used in the body
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<MirFields>,
+    /// Borrows created in the body
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub loans: Vec<MirLoan>,
+    /// Accesses which conflict with a live borrow, reported by the borrow checker
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<MirConflict>,
+    /// Named lifetime parameters in the signature
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lifetimes: Vec<MirLifetime>,
+}
+
+/// A borrow with the place it borrows and the local it is assigned to
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MirLoan {
+    /// Index of the borrow in the body
+    pub id: u32,
+    pub borrowed: MirPlace,
+    /// Local the reference is assigned to
+    pub holder: FnLocal,
+    pub mutable: bool,
+    /// Where the borrow is created
+    pub range: Option<Range>,
+    /// Why the borrow lives this long: where it flows into other references in order,
+    /// then the use which keeps it live. Empty if no such use is found.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chain: Vec<Range>,
+}
+
+/// An access to a borrowed place while the borrow is live, which fails to compile
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MirConflict {
+    /// `id` of the borrow in [`Function::loans`]
+    pub loan: u32,
+    pub access: MirAccessKind,
+    /// Where the borrowed place is accessed
+    pub range: Range,
+    /// Later use of a reference holding the borrow, which keeps it live at the access
+    pub used: Option<Range>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum MirAccessKind {
+    SharedBorrow,
+    MutableBorrow,
+    Move,
+    Assign,
+    Read,
+    /// The borrowed place goes out of scope or is dropped
+    Drop,
+}
+
+/// A named lifetime parameter, e.g. `'a` of `fn f<'a>(x: &'a T) -> &'a U`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MirLifetime {
+    pub name: String,
+    /// Where the lifetime is declared and used in the signature
+    pub spans: Vec<Range>,
+    /// Locals holding references which must outlive the lifetime
+    pub constrained: Vec<MirConstrainedLocal>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MirConstrainedLocal {
+    pub local: FnLocal,
+    /// Where the region of the local is live
+    pub ranges: Vec<Range>,
+}
+
+/// Live ranges of the borrows of a variable or a field of it
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MirFieldBorrow {
+    /// Field path of the borrowed place, which is empty if the whole variable is borrowed
+    pub fields: Vec<usize>,
+    pub mutable: bool,
+    pub ranges: Vec<Range>,
+}
+impl MirFieldBorrow {
+    /// Whether the borrowed places overlap, i.e. one of them contains the other
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.fields.iter().zip(&other.fields).all(|(a, b)| a == b)
+    }
+}
+
+/// Fields of a struct or tuple place
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MirFields {
+    pub local: FnLocal,
+    /// Field path of the place from the local
+    pub path: Vec<usize>,
+    pub names: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MirClosure {
+    /// `fn_id` of the enclosing function
+    pub parent: u32,
+    /// Local of the enclosing function which holds the closure
+    pub holder: Option<FnLocal>,
+    pub captures: Vec<MirCapture>,
+}
+
+/// Place of an enclosing function captured by a closure
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MirCapture {
+    /// Captured variable, which may be a local of an outer function for nested closures
+    pub local: FnLocal,
+    /// Captured place, e.g. `v` or `s.field`
+    pub place: String,
+    pub kind: MirCaptureKind,
+    /// Use in the closure which determines the capture kind
+    pub range: Range,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum MirCaptureKind {
+    ByRef,
+    ByMutRef,
+    ByMove,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn skipped(fn_id: u32, reason: SkipReason) -> Item {
+        Item::Skipped {
+            span: Range::new(Loc(0), Loc(1)).unwrap(),
+            fn_id,
+            name: format!("f{fn_id}"),
+            reason,
+        }
+    }
+
+    #[test]
+    fn merge_replaces_items_of_same_function() {
+        let mut krate = Crate(HashMap::from([(
+            "lib.rs".to_owned(),
+            File {
+                items: vec![
+                    skipped(1, SkipReason::TimedOut),
+                    skipped(2, SkipReason::TimedOut),
+                ],
+            },
+        )]));
+        for _ in 0..2 {
+            krate.merge(Crate(HashMap::from([(
+                "lib.rs".to_owned(),
+                File {
+                    items: vec![
+                        skipped(1, SkipReason::CompileError),
+                        skipped(3, SkipReason::CompileError),
+                    ],
+                },
+            )])));
+        }
+
+        let items = &krate.0["lib.rs"].items;
+        let reasons: Vec<_> = items
+            .iter()
+            .map(|v| match v {
+                Item::Skipped { fn_id, reason, .. } => (*fn_id, *reason),
+                Item::Function { .. } => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                (1, SkipReason::CompileError),
+                (2, SkipReason::TimedOut),
+                (3, SkipReason::CompileError),
+            ]
+        );
+    }
 }
@@ -1,4 +1,5 @@
 use std::env;
+use std::fmt;
 use std::fs::read_dir;
 use std::path::{Path, PathBuf};
 use std::sync::LazyLock;
@@ -24,6 +25,76 @@ pub static FALLBACK_RUNTIME_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
     env::home_dir().unwrap().join(".rustowl")
 });
 
+/// Errors that occur while setting up the toolchain
+#[derive(Debug)]
+pub enum ToolchainError {
+    /// Could not download an archive
+    Download {
+        url: String,
+        source: reqwest::Error,
+    },
+    /// Downloaded archive could not be unpacked
+    CorruptArchive {
+        url: String,
+        reason: String,
+    },
+    /// Filesystem operation failed
+    Io {
+        context: String,
+        source: std::io::Error,
+    },
+    /// Installation of a toolchain component failed
+    Component {
+        component: String,
+        source: Box<ToolchainError>,
+    },
+    /// Installation task could not be joined
+    Join(tokio::task::JoinError),
+    ProgressStyle(indicatif::style::TemplateError),
+}
+
+impl ToolchainError {
+    fn io(context: impl Into<String>) -> impl FnOnce(std::io::Error) -> Self {
+        let context = context.into();
+        move |source| Self::Io { context, source }
+    }
+}
+
+impl fmt::Display for ToolchainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToolchainError::Download { url, source } => {
+                write!(f, "failed to download {url}: {source}")
+            }
+            ToolchainError::CorruptArchive { url, reason } => {
+                write!(
+                    f,
+                    "failed to unpack archive downloaded from {url}: {reason}"
+                )
+            }
+            ToolchainError::Io { context, source } => write!(f, "{context}: {source}"),
+            ToolchainError::Component { component, source } => {
+                write!(f, "failed to install component {component}: {source}")
+            }
+            ToolchainError::Join(e) => write!(f, "failed to join installation task: {e}"),
+            ToolchainError::ProgressStyle(e) => write!(f, "failed to setup progress bar: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ToolchainError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ToolchainError::Download { source, .. } => Some(source),
+            ToolchainError::Io { source, .. } => Some(source),
+            ToolchainError::Component { source, .. } => Some(source),
+            ToolchainError::Join(e) => Some(e),
+            ToolchainError::ProgressStyle(e) => Some(e),
+            ToolchainError::CorruptArchive { .. } => None,
+        }
+    }
+}
+
 fn recursive_read_dir(path: impl AsRef<Path>) -> Vec<PathBuf> {
     let mut paths = Vec::new();
     if path.as_ref().is_dir() {
@@ -43,58 +114,45 @@ pub fn sysroot_from_runtime(runtime: impl AsRef<Path>) -> PathBuf {
     runtime.as_ref().join("sysroot").join(TOOLCHAIN)
 }
 
-async fn get_runtime_dir() -> PathBuf {
+async fn get_runtime_dir() -> Result<PathBuf, ToolchainError> {
     let sysroot = sysroot_from_runtime(&*FALLBACK_RUNTIME_DIR);
     if FALLBACK_RUNTIME_DIR.is_dir() && sysroot.is_dir() {
-        return FALLBACK_RUNTIME_DIR.clone();
+        return Ok(FALLBACK_RUNTIME_DIR.clone());
     }
 
     log::info!("sysroot not found; start setup toolchain");
-    if let Err(e) = setup_toolchain(&*FALLBACK_RUNTIME_DIR, false).await {
-        log::error!("{e:?}");
-        std::process::exit(1);
-    } else {
-        FALLBACK_RUNTIME_DIR.clone()
-    }
+    setup_toolchain(&*FALLBACK_RUNTIME_DIR, false).await?;
+    Ok(FALLBACK_RUNTIME_DIR.clone())
 }
 
-pub async fn get_sysroot() -> PathBuf {
-    sysroot_from_runtime(get_runtime_dir().await)
+pub async fn get_sysroot() -> Result<PathBuf, ToolchainError> {
+    Ok(sysroot_from_runtime(get_runtime_dir().await?))
 }
 
-fn progress_bar_style() -> Result<indicatif::ProgressStyle, ()> {
+fn progress_bar_style() -> Result<indicatif::ProgressStyle, ToolchainError> {
     use indicatif::*;
     Ok(
         ProgressStyle::with_template("{spinner:.green} {msg:<10} [{bar:30.cyan/blue}]  {pos:>3}%")
-            .map_err(|_| {
-                log::error!("failed to setup progress bar");
-            })?
+            .map_err(ToolchainError::ProgressStyle)?
             .progress_chars("#>-"),
     )
 }
 
-async fn download(url: &str, set_progress: impl Fn(usize)) -> Result<Vec<u8>, ()> {
+async fn download(url: &str, set_progress: impl Fn(usize)) -> Result<Vec<u8>, ToolchainError> {
     log::debug!("start downloading {url}...");
-    let mut resp = match reqwest::get(url).await.and_then(|v| v.error_for_status()) {
-        Ok(v) => v,
-        Err(e) => {
-            log::error!("failed to download tarball");
-            log::error!("{e:?}");
-            return Err(());
-        }
+    let download_error = |source| ToolchainError::Download {
+        url: url.to_owned(),
+        source,
     };
+    let mut resp = reqwest::get(url)
+        .await
+        .and_then(|v| v.error_for_status())
+        .map_err(download_error)?;
 
     let content_length = resp.content_length().unwrap_or(200_000_000) as usize;
     let mut data = Vec::with_capacity(content_length);
     let mut received = 0;
-    while let Some(chunk) = match resp.chunk().await {
-        Ok(v) => v,
-        Err(e) => {
-            log::error!("failed to download runtime archive");
-            log::error!("{e:?}");
-            return Err(());
-        }
-    } {
+    while let Some(chunk) = resp.chunk().await.map_err(download_error)? {
         data.extend_from_slice(&chunk);
         let current = data.len() * 100 / content_length;
         if received != current {
@@ -110,13 +168,16 @@ async fn download_tarball_and_extract(
     url: &str,
     dest: &Path,
     set_progress: impl Fn(usize),
-) -> Result<(), ()> {
+) -> Result<(), ToolchainError> {
     let data = download(url, set_progress).await?;
     let decoder = GzDecoder::new(&*data);
     let mut archive = Archive::new(decoder);
-    archive.unpack(dest).map_err(|_| {
-        log::error!("failed to unpack tarball");
-    })?;
+    archive
+        .unpack(dest)
+        .map_err(|e| ToolchainError::CorruptArchive {
+            url: url.to_owned(),
+            reason: e.to_string(),
+        })?;
     log::debug!("successfully unpacked");
     Ok(())
 }
@@ -125,22 +186,17 @@ async fn download_zip_and_extract(
     url: &str,
     dest: &Path,
     set_progress: impl Fn(usize),
-) -> Result<(), ()> {
+) -> Result<(), ToolchainError> {
     use zip::ZipArchive;
     let data = download(url, set_progress).await?;
     let cursor = std::io::Cursor::new(&*data);
 
-    let mut archive = match ZipArchive::new(cursor) {
-        Ok(archive) => archive,
-        Err(e) => {
-            log::error!("failed to read ZIP archive");
-            log::error!("{e:?}");
-            return Err(());
-        }
+    let corrupt = |e: zip::result::ZipError| ToolchainError::CorruptArchive {
+        url: url.to_owned(),
+        reason: e.to_string(),
     };
-    archive.extract(dest).map_err(|e| {
-        log::error!("failed to unpack zip: {e}");
-    })?;
+    let mut archive = ZipArchive::new(cursor).map_err(corrupt)?;
+    archive.extract(dest).map_err(corrupt)?;
     log::debug!("successfully unpacked");
     Ok(())
 }
@@ -148,7 +204,7 @@ async fn download_zip_and_extract(
 async fn install_components(
     components: impl IntoIterator<Item = impl AsRef<str>>,
     dest: PathBuf,
-) -> Result<(), ()> {
+) -> Result<(), ToolchainError> {
     use indicatif::*;
     let m = MultiProgress::new();
 
@@ -163,8 +219,10 @@ async fn install_components(
         pb.set_message(component.clone());
 
         let dest = dest.clone();
+        let name = component.clone();
         let handle = tokio::spawn(async move {
-            let tempdir = tempfile::tempdir().map_err(|_| ())?;
+            let tempdir =
+                tempfile::tempdir().map_err(ToolchainError::io("failed to create temp dir"))?;
             // Using `tempdir.path()` more than once causes SEGV, so we use `tempdir.path().to_owned()`.
             let temp_path = tempdir.path().to_owned();
             log::debug!("temp dir is made: {}", temp_path.display());
@@ -184,36 +242,39 @@ async fn install_components(
             let extracted_path = temp_path.join(&component_toolchain);
             let components = read_to_string(extracted_path.join("components"))
                 .await
-                .map_err(|_| {
-                    log::error!("failed to read components list");
-                })?;
+                .map_err(ToolchainError::io("failed to read components list"))?;
             let components = components.split_whitespace();
 
             for component in components {
                 let component_path = extracted_path.join(component);
                 for from in recursive_read_dir(&component_path) {
-                    let rel_path = match from.strip_prefix(&component_path) {
-                        Ok(v) => v,
-                        Err(e) => {
-                            log::error!("path error: {e}");
-                            return Err(());
+                    let rel_path = from.strip_prefix(&component_path).map_err(|e| {
+                        ToolchainError::CorruptArchive {
+                            url: tarball_url.clone(),
+                            reason: e.to_string(),
                         }
-                    };
+                    })?;
                     let to = dest.join(rel_path);
-                    if let Err(e) = create_dir_all(to.parent().unwrap()).await {
-                        log::error!("failed to create dir: {e}");
-                        return Err(());
-                    }
+                    create_dir_all(to.parent().unwrap())
+                        .await
+                        .map_err(ToolchainError::io(format!(
+                            "failed to create dir {}",
+                            to.display()
+                        )))?;
                     if let Err(e) = rename(&from, &to).await {
                         log::warn!("file rename failed: {e}, falling back to copy and delete");
-                        if let Err(copy_err) = tokio::fs::copy(&from, &to).await {
-                            log::error!("file copy error (after rename failure): {copy_err}");
-                            return Err(());
-                        }
-                        if let Err(del_err) = tokio::fs::remove_file(&from).await {
-                            log::error!("file delete error (after copy): {del_err}");
-                            return Err(());
-                        }
+                        tokio::fs::copy(&from, &to)
+                            .await
+                            .map_err(ToolchainError::io(format!(
+                                "failed to copy {}",
+                                to.display()
+                            )))?;
+                        tokio::fs::remove_file(&from)
+                            .await
+                            .map_err(ToolchainError::io(format!(
+                                "failed to delete {}",
+                                from.display()
+                            )))?;
                     }
                 }
                 log::debug!("component {component} successfully installed");
@@ -221,39 +282,50 @@ async fn install_components(
             pb.finish_and_clear();
             Ok(())
         });
-        threads.push(handle);
+        threads.push((name, handle));
     }
-    for thread in threads {
-        if let Ok(res) = thread.await {
-            if res.is_err() {
-                log::error!("failed to install component")
+    // wait for all installations before reporting the first error
+    let mut result = Ok(());
+    for (component, thread) in threads {
+        let res = thread
+            .await
+            .map_err(ToolchainError::Join)
+            .and_then(|v| v)
+            .map_err(|e| ToolchainError::Component {
+                component,
+                source: Box::new(e),
+            });
+        if let Err(e) = res {
+            log::error!("{e}");
+            if result.is_ok() {
+                result = Err(e);
             }
-        } else {
-            log::error!("failed to join component installation task");
         }
     }
-    Ok(())
+    result
 }
-pub async fn setup_toolchain(dest: impl AsRef<Path>, skip_rustowl: bool) -> Result<(), ()> {
+pub async fn setup_toolchain(
+    dest: impl AsRef<Path>,
+    skip_rustowl: bool,
+) -> Result<(), ToolchainError> {
     setup_rust_toolchain(&dest).await?;
     if !skip_rustowl {
         setup_rustowl_toolchain(&dest).await?;
     }
     Ok(())
 }
-pub async fn setup_rust_toolchain(dest: impl AsRef<Path>) -> Result<(), ()> {
+pub async fn setup_rust_toolchain(dest: impl AsRef<Path>) -> Result<(), ToolchainError> {
     let sysroot = sysroot_from_runtime(dest.as_ref());
-    if create_dir_all(&sysroot).await.is_err() {
-        log::error!("failed to create toolchain directory");
-        return Err(());
-    }
+    create_dir_all(&sysroot)
+        .await
+        .map_err(ToolchainError::io("failed to create toolchain directory"))?;
 
     log::info!("start installing Rust toolchain...");
     install_components(&["rustc", "rust-std", "cargo"], sysroot).await?;
     log::info!("installing Rust toolchain finished");
     Ok(())
 }
-pub async fn setup_rustowl_toolchain(dest: impl AsRef<Path>) -> Result<(), ()> {
+pub async fn setup_rustowl_toolchain(dest: impl AsRef<Path>) -> Result<(), ToolchainError> {
     let pb = indicatif::ProgressBar::new(100);
     pb.set_style(progress_bar_style()?);
 
@@ -281,57 +353,61 @@ pub async fn setup_rustowl_toolchain(dest: impl AsRef<Path>) -> Result<(), ()> {
         .await
     };
     pb.finish_and_clear();
-    if rustowl_toolchain_result.is_ok() {
-        log::info!("installing RustOwl toolchain finished");
-    } else {
+    if let Err(e) = rustowl_toolchain_result {
+        log::warn!("{e}");
         log::warn!("could not install RustOwl toolchain; local installed rustowlc will be used");
+    } else {
+        log::info!("installing RustOwl toolchain finished");
     }
     Ok(())
 }
 
-pub async fn uninstall_toolchain() {
+pub async fn uninstall_toolchain() -> Result<(), ToolchainError> {
     let sysroot = sysroot_from_runtime(&*FALLBACK_RUNTIME_DIR);
     if sysroot.is_dir() {
         log::info!("remove sysroot: {}", sysroot.display());
-        remove_dir_all(&sysroot).await.unwrap();
+        remove_dir_all(&sysroot)
+            .await
+            .map_err(ToolchainError::io("failed to remove sysroot"))?;
     }
+    Ok(())
 }
 
-pub async fn get_executable_path(name: &str) -> String {
+pub async fn get_executable_path(name: &str) -> Result<String, ToolchainError> {
     #[cfg(not(windows))]
     let exec_name = name.to_owned();
     #[cfg(windows)]
     let exec_name = format!("{name}.exe");
 
-    let runtime_dir = get_runtime_dir().await;
+    let runtime_dir = get_runtime_dir().await?;
     let exec_root = runtime_dir.join(&exec_name);
     if exec_root.is_file() {
         log::debug!("{name} is selected in runtime root");
-        return exec_root.to_string_lossy().to_string();
+        return Ok(exec_root.to_string_lossy().to_string());
     }
 
-    let sysroot = get_sysroot().await;
+    let sysroot = get_sysroot().await?;
     let exec_bin = sysroot.join("bin").join(&exec_name);
     if exec_bin.is_file() {
         log::debug!("{name} is selected in sysroot/bin");
-        return exec_bin.to_string_lossy().to_string();
+        return Ok(exec_bin.to_string_lossy().to_string());
     }
 
     let mut current_exec = env::current_exe().unwrap();
     current_exec.set_file_name(&exec_name);
     if current_exec.is_file() {
         log::debug!("{name} is selected in the same directory as rustowl executable");
-        return current_exec.to_string_lossy().to_string();
+        return Ok(current_exec.to_string_lossy().to_string());
     }
 
     log::warn!("{name} not found; fallback");
-    exec_name.to_owned()
+    Ok(exec_name.to_owned())
 }
 
-pub async fn setup_cargo_command() -> tokio::process::Command {
-    let cargo = get_executable_path("cargo").await;
+pub async fn setup_cargo_command() -> Result<tokio::process::Command, ToolchainError> {
+    let cargo = get_executable_path("cargo").await?;
     let mut command = tokio::process::Command::new(&cargo);
-    let rustowlc = get_executable_path("rustowlc").await;
+    let rustowlc = get_executable_path("rustowlc").await?;
 
     // check user set flags
     let delimiter = 0x1f as char;
@@ -343,7 +419,7 @@ pub async fn setup_cargo_command() -> tokio::process::Command {
         .map(|v| format!("{v}{delimiter}"))
         .unwrap_or("".to_string());
 
-    let sysroot = get_sysroot().await;
+    let sysroot = get_sysroot().await?;
     // use `RUSTOWLC` and `RUSTOWLC_WORKSPACE_WRAPPER` env var to configure `rustowlc` path
     let rustowlc = env::var("RUSTOWLC").unwrap_or(rustowlc);
     let rustowlc_workspace = env::var("RUSTOWLC_WORKSPACE_WRAPPER").unwrap_or(rustowlc.clone());
@@ -360,7 +436,7 @@ pub async fn setup_cargo_command() -> tokio::process::Command {
             ),
         );
     set_rustc_env(&mut command, &sysroot);
-    command
+    Ok(command)
 }
 
 pub fn set_rustc_env(command: &mut tokio::process::Command, sysroot: &Path) {
@@ -4,7 +4,9 @@
 //! and lifetime information, using colored underlines to represent
 //! different ownership states.
 
+use crate::config::DecorationConfig;
 use crate::lsp::decoration::{CalcDecos, Deco};
+use crate::lsp::explain::{self, BorrowStep};
 use crate::models::*;
 use crate::utils::{self, MirVisitor};
 use std::collections::HashMap;
@@ -43,6 +45,8 @@ pub(crate) mod colors {
 pub enum VisualizeError {
     FileNotFound(String),
     FunctionNotFound(String),
+    /// The function was skipped by the analysis
+    FunctionNotAnalyzed(String, SkipReason),
     VariableNotFound(String),
     SourceReadError(std::io::Error),
 }
@@ -52,6 +56,9 @@ impl fmt::Display for VisualizeError {
         match self {
             VisualizeError::FileNotFound(path) => write!(f, "File not found: {path}"),
             VisualizeError::FunctionNotFound(name) => write!(f, "Function not found: {name}"),
+            VisualizeError::FunctionNotAnalyzed(name, reason) => {
+                write!(f, "Function not analyzed: {name} ({reason})")
+            }
             VisualizeError::VariableNotFound(name) => write!(f, "Variable not found: {name}"),
             VisualizeError::SourceReadError(e) => write!(f, "Failed to read source file: {e}"),
         }
@@ -189,6 +196,25 @@ impl<'a> FindVariablesByName<'a> {
 impl MirVisitor for FindVariablesByName<'_> {
     fn visit_func(&mut self, func: &Function) {
         self.current_function_name = func.name.clone();
+        if !self.matches_function(&self.current_function_name) {
+            return;
+        }
+
+        // a lifetime parameter has no local, so the return place stands for the function
+        for lifetime in func
+            .lifetimes
+            .iter()
+            .filter(|v| v.name == self.variable_name)
+        {
+            if let Some(span) = lifetime.spans.first() {
+                self.found.push(VariableInfo {
+                    local: FnLocal::new(0, func.fn_id),
+                    name: lifetime.name.clone(),
+                    span: *span,
+                    function_name: self.current_function_name.clone(),
+                });
+            }
+        }
     }
 
     fn visit_decl(&mut self, decl: &MirDecl) {
@@ -215,39 +241,53 @@ impl Deco {
     const COLOR_LIFETIME: anstyle::Style = colors::GREEN;
     const COLOR_IMMUTABLE: anstyle::Style = colors::CYAN;
     const COLOR_MUTABLE: anstyle::Style = colors::PURPLE;
+    const COLOR_RAW_PTR: anstyle::Style = colors::MAGENTA;
     const COLOR_MOVE: anstyle::Style = colors::YELLOW;
     const COLOR_CALL: anstyle::Style = colors::YELLOW;
+    const COLOR_AWAIT: anstyle::Style = colors::RED;
     const COLOR_SHARED: anstyle::Style = colors::RED;
     const COLOR_OUTLIVE: anstyle::Style = colors::RED;
+    const COLOR_CONFLICT: anstyle::Style = colors::RED;
 
     fn color(&self) -> anstyle::Style {
         match self {
             Deco::Lifetime { .. } => Self::COLOR_LIFETIME,
             Deco::DefinitelyLive { .. } => Self::COLOR_LIFETIME,
             Deco::MaybeInitialized { .. } => Self::COLOR_LIFETIME,
+            Deco::LifetimeParam { .. } => Self::COLOR_LIFETIME,
+            Deco::Read { .. } => Self::COLOR_IMMUTABLE,
             Deco::ImmBorrow { .. } => Self::COLOR_IMMUTABLE,
             Deco::MutBorrow { .. } => Self::COLOR_MUTABLE,
+            Deco::RawPtr { .. } => Self::COLOR_RAW_PTR,
             Deco::Move { .. } => Self::COLOR_MOVE,
             Deco::Call { .. } => Self::COLOR_CALL,
+            Deco::HeldAcrossAwait { .. } => Self::COLOR_AWAIT,
             Deco::SharedMut { .. } => Self::COLOR_SHARED,
             Deco::Outlive { .. } => Self::COLOR_OUTLIVE,
+            Deco::Conflict { .. } => Self::COLOR_CONFLICT,
         }
     }
 
     const LINE_SOLID: char = '-';
     const LINE_WAVY: char = '~';
+    const LINE_DOTTED: char = '.';
 
     fn underline_char(&self) -> char {
         match self {
             Deco::Lifetime { .. } => Self::LINE_WAVY,
             Deco::DefinitelyLive { .. } => Self::LINE_SOLID,
             Deco::MaybeInitialized { .. } => Self::LINE_WAVY,
+            Deco::LifetimeParam { .. } => Self::LINE_SOLID,
+            Deco::Read { .. } => Self::LINE_DOTTED,
             Deco::ImmBorrow { .. } => Self::LINE_SOLID,
             Deco::MutBorrow { .. } => Self::LINE_SOLID,
+            Deco::RawPtr { .. } => Self::LINE_SOLID,
             Deco::Move { .. } => Self::LINE_SOLID,
             Deco::Call { .. } => Self::LINE_SOLID,
+            Deco::HeldAcrossAwait { .. } => Self::LINE_WAVY,
             Deco::SharedMut { .. } => Self::LINE_WAVY,
             Deco::Outlive { .. } => Self::LINE_WAVY,
+            Deco::Conflict { .. } => Self::LINE_SOLID,
         }
     }
 }
@@ -274,9 +314,13 @@ impl<'a> CliRenderer<'a> {
     ) {
         // Print header
         let style = colors::CYAN;
+        let subject = if var_info.name.starts_with('\'') {
+            format!("Lifetime parameter {}", var_info.name)
+        } else {
+            format!("Variable '{}'", var_info.name)
+        };
         println!(
-            "\n{style}=== Variable '{}' ({}/{}) in function '{}' ==={style:#}\n",
-            var_info.name,
+            "\n{style}=== {subject} ({}/{}) in function '{}' ==={style:#}\n",
             var_index + 1,
             total_vars,
             var_info.function_name,
@@ -297,23 +341,38 @@ impl<'a> CliRenderer<'a> {
                 | Deco::MaybeInitialized {
                     range, overlapped, ..
                 }
+                | Deco::LifetimeParam {
+                    range, overlapped, ..
+                }
+                | Deco::Read {
+                    range, overlapped, ..
+                }
                 | Deco::ImmBorrow {
                     range, overlapped, ..
                 }
                 | Deco::MutBorrow {
                     range, overlapped, ..
                 }
+                | Deco::RawPtr {
+                    range, overlapped, ..
+                }
                 | Deco::Move {
                     range, overlapped, ..
                 }
                 | Deco::Call {
                     range, overlapped, ..
                 }
+                | Deco::HeldAcrossAwait {
+                    range, overlapped, ..
+                }
                 | Deco::SharedMut {
                     range, overlapped, ..
                 }
                 | Deco::Outlive {
                     range, overlapped, ..
+                }
+                | Deco::Conflict {
+                    range, overlapped, ..
                 } => (*range, *overlapped),
             };
             if overlapped {
@@ -384,6 +443,55 @@ impl<'a> CliRenderer<'a> {
         println!();
     }
 
+    /// Render the steps explaining why a borrow lives as long as it does.
+    pub fn render_explanation(&self, var_info: &VariableInfo, steps: &[BorrowStep<Range>]) {
+        let Some(first) = steps.first() else {
+            return;
+        };
+        let (line, _) = utils::index_to_line_char(self.source, first.range.from());
+        let style = colors::CYAN;
+        println!(
+            "{style}--- Why the borrow of '{}' at line {} lives ---{style:#}\n",
+            var_info.name,
+            line + 1,
+        );
+
+        let dim = colors::DIM;
+        for (i, step) in steps.iter().enumerate() {
+            let (start_line, start_col) = utils::index_to_line_char(self.source, step.range.from());
+            let (end_line, end_col) = utils::index_to_line_char(self.source, step.range.until());
+            let Some(line_content) = self.lines.get(start_line as usize) else {
+                continue;
+            };
+            // only the first line of a multi-line range is shown
+            let end_col = if start_line == end_line {
+                end_col
+            } else {
+                line_content.chars().count() as u32
+            };
+            println!(
+                "{dim}{:4} |{dim:#} {}",
+                start_line + 1,
+                syntax::highlight(line_content),
+            );
+            let color = if i + 1 == steps.len() {
+                Deco::COLOR_OUTLIVE
+            } else {
+                Deco::COLOR_IMMUTABLE
+            };
+            let space: String = (0..start_col).map(|_| ' ').collect();
+            let line: String = (start_col..end_col.max(start_col + 1))
+                .map(|_| '^')
+                .collect();
+            println!(
+                "{dim}     |{dim:#} {space}{color}{line} {}. {}{color:#}",
+                i + 1,
+                step.message
+            );
+        }
+        println!();
+    }
+
     /// Print decoration underlines for a single line.
     /// Groups decorations of the same type on the same output line.
     fn print_decorations(&self, decos: &mut [(u32, u32, Deco)]) {
@@ -451,6 +559,29 @@ fn normalize_file_path(path: &Path) -> String {
     normalized
 }
 
+/// Find a function matching the path which was skipped by the analysis
+fn find_skipped(
+    crate_data: &Crate,
+    file_path: Option<&Path>,
+    function_path: &str,
+) -> Option<(String, SkipReason)> {
+    let matcher = FindVariablesByName::new(function_path, "");
+    let files: Vec<&File> = if let Some(path) = file_path {
+        find_file(crate_data, path).into_iter().collect()
+    } else {
+        crate_data.0.values().collect()
+    };
+    files
+        .into_iter()
+        .flat_map(|file| &file.items)
+        .find_map(|item| match item {
+            Item::Skipped { name, reason, .. } if matcher.matches_function(name) => {
+                Some((name.clone(), *reason))
+            }
+            _ => None,
+        })
+}
+
 /// Main entry point for CLI visualization with optional file path.
 ///
 /// Shows ownership and lifetime visualization for a specific variable
@@ -460,6 +591,8 @@ pub fn show_variable(
     file_path: Option<&Path>,
     function_path: &str,
     variable_name: &str,
+    decoration: &DecorationConfig,
+    explain: bool,
 ) -> Result<(), VisualizeError> {
     // Collect all matching variables across files
     let mut all_found: Vec<(String, VariableInfo)> = Vec::new();
@@ -470,7 +603,7 @@ pub fn show_variable(
             .ok_or_else(|| VisualizeError::FileNotFound(path.display().to_string()))?;
 
         let mut finder = FindVariablesByName::new(function_path, variable_name);
-        for func in &file.items {
+        for func in file.items.iter().filter_map(Item::function) {
             utils::mir_visit(func, &mut finder);
         }
 
@@ -481,7 +614,7 @@ pub fn show_variable(
         // Search in all files
         for (file_path_str, file) in &crate_data.0 {
             let mut finder = FindVariablesByName::new(function_path, variable_name);
-            for func in &file.items {
+            for func in file.items.iter().filter_map(Item::function) {
                 utils::mir_visit(func, &mut finder);
             }
 
@@ -490,8 +623,16 @@ pub fn show_variable(
             }
         }
     }
+    // functions and files are not ordered in the analysis results,
+    // and a captured variable has the same span in the closure as in its parent
+    all_found.sort_by(|(a_file, a), (b_file, b)| {
+        (a_file, a.span.from(), &a.function_name).cmp(&(b_file, b.span.from(), &b.function_name))
+    });
 
     if all_found.is_empty() {
+        if let Some((name, reason)) = find_skipped(crate_data, file_path, function_path) {
+            return Err(VisualizeError::FunctionNotAnalyzed(name, reason));
+        }
         return Err(VisualizeError::VariableNotFound(format!(
             "'{variable_name}' in function '{function_path}'"
         )));
@@ -512,15 +653,36 @@ pub fn show_variable(
         let source = std::fs::read_to_string(file_path)?;
         let renderer = CliRenderer::new(&source);
 
-        // Calculate decorations for this variable
-        let mut calc = CalcDecos::new(std::iter::once(var_info.local));
-        for func in &file.items {
+        // Calculate decorations for this variable, or for the lifetime parameter
+        let is_lifetime = var_info.name.starts_with('\'');
+        let mut calc = if is_lifetime {
+            let mut calc = CalcDecos::new([]);
+            calc.select_lifetime(var_info.local.fn_id, &var_info.name);
+            calc
+        } else {
+            CalcDecos::new(std::iter::once(var_info.local))
+        };
+        for func in file.items.iter().filter_map(Item::function) {
             utils::mir_visit(func, &mut calc);
         }
         calc.handle_overlapping();
-        let decos = calc.decorations();
+        let mut decos = calc.decorations();
+        decos.retain(|v| !decoration.is_hidden(v.kind()));
 
         renderer.render_variable(var_info, idx, total_vars, &decos);
+
+        if explain && !is_lifetime {
+            let functions = file.items.iter().filter_map(Item::function);
+            for func in functions.filter(|v| v.fn_id == var_info.local.fn_id) {
+                for loan in func
+                    .loans
+                    .iter()
+                    .filter(|v| v.borrowed.local == var_info.local)
+                {
+                    renderer.render_explanation(var_info, &explain::explain(func, loan));
+                }
+            }
+        }
     }
 
     // Print legend
@@ -535,8 +697,11 @@ fn print_legend() {
     let lifetime = Deco::COLOR_LIFETIME;
     let immutable = Deco::COLOR_IMMUTABLE;
     let mutable = Deco::COLOR_MUTABLE;
+    let raw_ptr = Deco::COLOR_RAW_PTR;
     let mov = Deco::COLOR_MOVE;
+    let held = Deco::COLOR_AWAIT;
     let red = Deco::COLOR_OUTLIVE;
+    let conflict = Deco::COLOR_CONFLICT;
 
     println!("{cyan}Legend:{cyan:#}");
     println!(
@@ -557,24 +722,48 @@ fn print_legend() {
         Deco::LINE_SOLID,
         Deco::LINE_SOLID,
     );
+    println!(
+        "  {immutable}{}{}{}{immutable:#} read without moving (`match`, `let _ =`)",
+        Deco::LINE_DOTTED,
+        Deco::LINE_DOTTED,
+        Deco::LINE_DOTTED,
+    );
     println!(
         "  {mutable}{}{}{}{mutable:#} mutable borrow",
         Deco::LINE_SOLID,
         Deco::LINE_SOLID,
         Deco::LINE_SOLID,
     );
+    println!(
+        "  {raw_ptr}{}{}{}{raw_ptr:#} raw pointer taken",
+        Deco::LINE_SOLID,
+        Deco::LINE_SOLID,
+        Deco::LINE_SOLID,
+    );
     println!(
         "  {mov}{}{}{}{mov:#} move / call",
         Deco::LINE_SOLID,
         Deco::LINE_SOLID,
         Deco::LINE_SOLID,
     );
+    println!(
+        "  {held}{}{}{}{held:#} held across `.await`",
+        Deco::LINE_WAVY,
+        Deco::LINE_WAVY,
+        Deco::LINE_WAVY,
+    );
     println!(
         "  {red}{}{}{}{red:#} outlive / shared mutable",
         Deco::LINE_WAVY,
         Deco::LINE_WAVY,
         Deco::LINE_WAVY,
     );
+    println!(
+        "  {conflict}{}{}{}{conflict:#} borrow-check conflict",
+        Deco::LINE_SOLID,
+        Deco::LINE_SOLID,
+        Deco::LINE_SOLID,
+    );
 }
 
 #[cfg(test)]
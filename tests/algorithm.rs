@@ -24,6 +24,10 @@ fn ensure_rustowl_built() {
 }
 
 fn get_rustowl_output(function_path: &str, variable: &str) -> String {
+    get_rustowl_output_with(function_path, variable, &[])
+}
+
+fn rustowl_command() -> (String, Command) {
     ensure_rustowl_built();
 
     let exe_name = if cfg!(windows) {
@@ -44,19 +48,24 @@ fn get_rustowl_output(function_path: &str, variable: &str) -> String {
         std::path::MAIN_SEPARATOR,
         exe_name
     );
+    let command = Command::new(&rustowl_path);
+    (rustowl_path, command)
+}
 
-    let output = Command::new(&rustowl_path)
-        .args([
-            "show",
-            "--path",
-            &format!(
-                "algo-tests{}src{}vec.rs",
-                std::path::MAIN_SEPARATOR,
-                std::path::MAIN_SEPARATOR
-            ),
-            function_path,
-            variable,
-        ])
+fn source_path(file: &str) -> String {
+    format!(
+        "algo-tests{}src{}{file}",
+        std::path::MAIN_SEPARATOR,
+        std::path::MAIN_SEPARATOR
+    )
+}
+
+fn get_rustowl_output_with(function_path: &str, variable: &str, options: &[&str]) -> String {
+    let (rustowl_path, mut command) = rustowl_command();
+    let output = command
+        .arg("show")
+        .args(options)
+        .args(["--path", &source_path("vec.rs"), function_path, variable])
         .output()
         .unwrap_or_else(|e| panic!("Failed to execute {rustowl_path}: {e}"));
 
@@ -116,3 +125,85 @@ fn test_f5_r() {
     let output = get_rustowl_output("vec::f5", "r");
     insta::assert_snapshot!(output);
 }
+
+#[test]
+fn test_f6_v1() {
+    let output = get_rustowl_output("vec::f6", "v1");
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_f6_first() {
+    let output = get_rustowl_output("vec::f6", "first");
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_f7_v1() {
+    let output = get_rustowl_output("vec::f7", "v1");
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_f8_v1() {
+    let output = get_rustowl_output("vec::f8", "v1");
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_f8_v2() {
+    let output = get_rustowl_output("vec::f8", "v2");
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_f9_v1() {
+    let output = get_rustowl_output("vec::f9", "v1");
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_f10_v1_explain() {
+    let output = get_rustowl_output_with("vec::f10", "v1", &["--explain"]);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_f11_v1() {
+    let output = get_rustowl_output("vec::f11", "v1");
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_f12_v1_explain() {
+    let output = get_rustowl_output_with("vec::f12", "v1", &["--explain"]);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_f13_lifetime_a() {
+    let output = get_rustowl_output("vec::f13", "'a");
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_macro_generated_function_skipped() {
+    let (rustowl_path, mut command) = rustowl_command();
+    let output = command
+        .args([
+            "show",
+            "--path",
+            &source_path("macros.rs"),
+            "macros::COUNTER",
+            "x",
+        ])
+        .output()
+        .unwrap_or_else(|e| panic!("Failed to execute {rustowl_path}: {e}"));
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Function not analyzed: macros::COUNTER") && stderr.contains("(no source)"),
+        "stderr: {stderr}"
+    );
+}
@@ -80,6 +80,7 @@ export async function activate(context: vscode.ExtensionContext) {
   let imBorrowDecorationType = vscode.window.createTextEditorDecorationType({});
   let mBorrowDecorationType = vscode.window.createTextEditorDecorationType({});
   let outLiveDecorationType = vscode.window.createTextEditorDecorationType({});
+  let conflictDecorationType = vscode.window.createTextEditorDecorationType({});
   let emptyDecorationType = vscode.window.createTextEditorDecorationType({});
 
   // update decoration
@@ -140,6 +141,10 @@ export async function activate(context: vscode.ExtensionContext) {
       highlightBackground,
       "wavy",
     );
+    conflictDecorationType = createDecorationType(
+      outliveColor,
+      highlightBackground,
+    );
     emptyDecorationType = vscode.window.createTextEditorDecorationType({});
 
     const lifetime: vscode.DecorationOptions[] = [];
@@ -149,6 +154,7 @@ export async function activate(context: vscode.ExtensionContext) {
     const mut: vscode.DecorationOptions[] = [];
     const moveCall: vscode.DecorationOptions[] = [];
     const outlive: vscode.DecorationOptions[] = [];
+    const conflict: vscode.DecorationOptions[] = [];
     const messages: vscode.DecorationOptions[] = [];
     for (const deco of data.decorations) {
       const range = rangeToRange(deco.range);
@@ -157,18 +163,27 @@ export async function activate(context: vscode.ExtensionContext) {
           lifetime.push({
             range,
           });
-        } else if (deco.type === "definitely_live") {
+        } else if (
+          deco.type === "definitely_live" ||
+          deco.type === "lifetime_param"
+        ) {
           definitely.push({ range });
         } else if (deco.type === "maybe_initialized") {
           maybe_init.push({ range });
-        } else if (deco.type === "imm_borrow") {
+        } else if (deco.type === "imm_borrow" || deco.type === "read") {
           immut.push({ range });
-        } else if (deco.type === "mut_borrow") {
+        } else if (deco.type === "mut_borrow" || deco.type === "raw_ptr") {
           mut.push({ range });
         } else if (deco.type === "call" || deco.type === "move") {
           moveCall.push({ range });
-        } else if (deco.type === "shared_mut" || deco.type === "outlive") {
+        } else if (
+          deco.type === "shared_mut" ||
+          deco.type === "outlive" ||
+          deco.type === "held_across_await"
+        ) {
           outlive.push({ range });
+        } else if (deco.type === "conflict") {
+          conflict.push({ range });
         }
       }
       if (
@@ -186,6 +201,7 @@ export async function activate(context: vscode.ExtensionContext) {
     editor.setDecorations(mBorrowDecorationType, mut);
     editor.setDecorations(moveDecorationType, moveCall);
     editor.setDecorations(outLiveDecorationType, outlive);
+    editor.setDecorations(conflictDecorationType, conflict);
     editor.setDecorations(emptyDecorationType, messages);
   };
   const resetDecoration = () => {
@@ -195,6 +211,7 @@ export async function activate(context: vscode.ExtensionContext) {
     imBorrowDecorationType.dispose();
     mBorrowDecorationType.dispose();
     outLiveDecorationType.dispose();
+    conflictDecorationType.dispose();
     emptyDecorationType.dispose();
   };
 
@@ -106,12 +106,17 @@ export const zLspType = z.union([
   z.literal("lifetime"),
   z.literal("definitely_live"),
   z.literal("maybe_initialized"),
+  z.literal("read"),
   z.literal("imm_borrow"),
   z.literal("mut_borrow"),
+  z.literal("raw_ptr"),
   z.literal("move"),
   z.literal("call"),
+  z.literal("held_across_await"),
   z.literal("shared_mut"),
   z.literal("outlive"),
+  z.literal("conflict"),
+  z.literal("lifetime_param"),
 ]);
 export const zLspCursorResponse = z.object({
   is_analyzed: z.boolean(),